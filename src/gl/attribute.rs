@@ -0,0 +1,157 @@
+use web_sys::WebGlRenderingContext as Context;
+use num_enum::{
+    TryFromPrimitive,
+    IntoPrimitive,
+};
+
+use super::error::GlError;
+use super::program::Program;
+use super::data_buffer::ArrayBuffer;
+use super::settings::AttributeSetting;
+use super::pod::Pod;
+use super::pod::bytes_of;
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, TryFromPrimitive, IntoPrimitive, PartialEq, Eq)]
+pub enum AttributeType {
+    Byte = Context::BYTE,
+    UnsignedByte = Context::UNSIGNED_BYTE,
+    Short = Context::SHORT,
+    UnsignedShort = Context::UNSIGNED_SHORT,
+    Float = Context::FLOAT,
+}
+
+impl AttributeType {
+    pub fn size_in_bytes(&self) -> u32 {
+        match self {
+            AttributeType::Byte | AttributeType::UnsignedByte => 1,
+            AttributeType::Short | AttributeType::UnsignedShort => 2,
+            AttributeType::Float => 4,
+        }
+    }
+}
+
+/**
+ * How a vertex attribute is read from its buffer. Stride and offset are in bytes,
+ * non zero divisor makes the attribute advance per instance instead of per vertex.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AttributeLayout {
+    pub size: u32,
+    pub data_type: AttributeType,
+    pub normalized: bool,
+    pub stride: u32,
+    pub offset: u32,
+    pub divisor: u32,
+}
+
+impl AttributeLayout {
+    /**
+     * Tightly packed float components, `size` per vertex.
+     */
+    pub fn floats(size: u32) -> AttributeLayout {
+        AttributeLayout {
+            size: size,
+            data_type: AttributeType::Float,
+            normalized: false,
+            stride: 0,
+            offset: 0,
+            divisor: 0,
+        }
+    }
+
+    pub fn stride(self, stride: u32) -> AttributeLayout {
+        AttributeLayout { stride: stride, ..self }
+    }
+
+    pub fn offset(self, offset: u32) -> AttributeLayout {
+        AttributeLayout { offset: offset, ..self }
+    }
+
+    pub fn divisor(self, divisor: u32) -> AttributeLayout {
+        AttributeLayout { divisor: divisor, ..self }
+    }
+}
+
+/**
+ * Interleaved vertex layout described by named components in order. Offsets follow `#[repr(C)]`
+ * rules, so a vertex struct with fields of the same types in the same order matches it.
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VertexFormat {
+    pub(self) components: Vec<(String, AttributeLayout)>,
+    pub(self) end: u32,
+    pub(self) alignment: u32,
+}
+
+impl VertexFormat {
+    pub fn new() -> VertexFormat {
+        VertexFormat::default()
+    }
+
+    pub fn component(mut self, name: &str, data_type: AttributeType, size: u32, normalized: bool) -> VertexFormat {
+        let align = data_type.size_in_bytes();
+        let offset = (self.end + align - 1) / align * align;
+        self.components.push((name.to_string(), AttributeLayout {
+            size: size,
+            data_type: data_type,
+            normalized: normalized,
+            stride: 0,
+            offset: offset,
+            divisor: 0,
+        }));
+        self.end = offset + size * data_type.size_in_bytes();
+        self.alignment = std::cmp::max(self.alignment, align);
+        return self;
+    }
+
+    pub fn float(self, name: &str, size: u32) -> VertexFormat {
+        self.component(name, AttributeType::Float, size, false)
+    }
+
+    /**
+     * Size of one vertex in bytes, including trailing padding.
+     */
+    pub fn stride(&self) -> u32 {
+        let align = std::cmp::max(self.alignment, 1);
+        return (self.end + align - 1) / align * align;
+    }
+
+    pub fn layout(&self, name: &str) -> Option<AttributeLayout> {
+        self.components.iter()
+            .find(|(component, _)| component == name)
+            .map(|(_, layout)| layout.stride(self.stride()))
+    }
+
+    pub fn components(&self) -> Vec<(String, AttributeLayout)> {
+        self.components.iter()
+            .map(|(name, layout)| (name.clone(), layout.stride(self.stride())))
+            .collect()
+    }
+
+    /**
+     * Bytes of `#[repr(C)]` vertices, the vertex size must be the format stride.
+     */
+    pub fn pack<T: Pod>(&self, vertices: &[T]) -> Result<Vec<u8>, GlError> {
+        let vertex_size = std::mem::size_of::<T>();
+        if vertex_size != self.stride() as usize {
+            return Err(GlError::DataSize {
+                expected: self.stride() as usize,
+                actual: vertex_size,
+            });
+        }
+        return Ok(bytes_of(vertices).to_vec());
+    }
+
+    /**
+     * Attribute bindings of the components the program uses, reading from the buffer.
+     * Use with `Settings::attributes`.
+     */
+    pub fn attributes(&self, program: &Program, buffer: &ArrayBuffer) -> Vec<AttributeSetting> {
+        self.components.iter()
+            .filter_map(|(name, layout)| program.attribute_location(name).map(|location| {
+                AttributeSetting::new(location, buffer.clone(), layout.stride(self.stride()))
+            }))
+            .collect()
+    }
+}
@@ -0,0 +1,74 @@
+use super::gl::Gl;
+use super::data_buffer::ArrayBuffer;
+use super::data_buffer::BufferUsage;
+use super::error::GlError;
+use super::pod::Pod;
+
+/**
+ * Bump allocator over one large `ArrayBuffer`, many small meshes share the buffer instead of each having its own.
+ * `alloc` writes the data after the previous allocation and returns its byte range, draw it by passing
+ * the offset to `AttributeLayout::offset`. `reset` frees everything at once, e.g. every frame for transient geometry.
+ */
+#[derive(Debug)]
+pub struct BufferArena {
+    pub(self) buffer: ArrayBuffer,
+    pub(self) offset: usize,
+    pub(self) high_water_mark: usize,
+}
+
+impl BufferArena {
+    /**
+     * Allocations start at multiples of 4 bytes, attribute offsets have to be aligned to the component size.
+     */
+    const ALIGNMENT: usize = 4;
+
+    pub fn new(gl: Gl, capacity_bytes: usize, usage: BufferUsage) -> BufferArena {
+        BufferArena {
+            buffer: ArrayBuffer::with_capacity(gl, capacity_bytes, usage),
+            offset: 0,
+            high_water_mark: 0,
+        }
+    }
+
+    /**
+     * Write the data into the arena, returns its `(offset, len)` in bytes. Fails with `BufferRange`
+     * when the data doesn't fit, the arena isn't grown.
+     */
+    pub fn alloc<T: Pod>(&mut self, data: &[T]) -> Result<(usize, usize), GlError> {
+        let offset = (self.offset + BufferArena::ALIGNMENT - 1) / BufferArena::ALIGNMENT * BufferArena::ALIGNMENT;
+        let len = std::mem::size_of_val(data);
+        self.buffer.write_sub_data(offset, data)?;
+        self.offset = offset + len;
+        self.high_water_mark = self.high_water_mark.max(self.offset);
+        return Ok((offset, len));
+    }
+
+    /**
+     * Free all allocations, the buffer content is overwritten by the following ones.
+     */
+    pub fn reset(&mut self) {
+        self.offset = 0;
+    }
+
+    pub fn buffer(&self) -> ArrayBuffer {
+        self.buffer.clone()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buffer.len_bytes()
+    }
+
+    /**
+     * Bytes allocated since the last `reset`.
+     */
+    pub fn used(&self) -> usize {
+        self.offset
+    }
+
+    /**
+     * Most bytes ever used at once, to size the arena.
+     */
+    pub fn high_water_mark(&self) -> usize {
+        self.high_water_mark
+    }
+}
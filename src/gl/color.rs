@@ -0,0 +1,77 @@
+/**
+ * RGBA color with channels in 0..1, not premultiplied unless produced by `to_premultiplied`.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Color {
+    pub red: f32,
+    pub green: f32,
+    pub blue: f32,
+    pub alpha: f32,
+}
+
+impl Color {
+    pub fn rgba_f32(red: f32, green: f32, blue: f32, alpha: f32) -> Color {
+        Color {
+            red: red,
+            green: green,
+            blue: blue,
+            alpha: alpha,
+        }
+    }
+
+    /**
+     * Channels in 0..255.
+     */
+    pub fn rgba_u8(red: u8, green: u8, blue: u8, alpha: u8) -> Color {
+        Color::rgba_f32(
+            red as f32 / 255.0,
+            green as f32 / 255.0,
+            blue as f32 / 255.0,
+            alpha as f32 / 255.0,
+        )
+    }
+
+    /**
+     * Color from `0xRRGGBBAA`, alpha is the lowest byte.
+     */
+    pub fn from_hex(hex: u32) -> Color {
+        let [red, green, blue, alpha] = hex.to_be_bytes();
+        Color::rgba_u8(red, green, blue, alpha)
+    }
+
+    pub fn to_premultiplied(&self) -> Color {
+        Color::rgba_f32(self.red * self.alpha, self.green * self.alpha, self.blue * self.alpha, self.alpha)
+    }
+
+    pub fn to_f32_array(&self) -> [f32; 4] {
+        [self.red, self.green, self.blue, self.alpha]
+    }
+
+    /**
+     * Channels in 0..255, values out of 0..1 are clamped.
+     */
+    pub fn to_u8_array(&self) -> [u8; 4] {
+        let byte = |value: f32| (value.max(0.0).min(1.0) * 255.0).round() as u8;
+        [byte(self.red), byte(self.green), byte(self.blue), byte(self.alpha)]
+    }
+}
+
+impl From<[f32; 4]> for Color {
+    fn from(value: [f32; 4]) -> Self {
+        let [red, green, blue, alpha] = value;
+        Color::rgba_f32(red, green, blue, alpha)
+    }
+}
+
+impl From<[u8; 4]> for Color {
+    fn from(value: [u8; 4]) -> Self {
+        let [red, green, blue, alpha] = value;
+        Color::rgba_u8(red, green, blue, alpha)
+    }
+}
+
+impl From<Color> for [f32; 4] {
+    fn from(value: Color) -> Self {
+        value.to_f32_array()
+    }
+}
@@ -0,0 +1,69 @@
+use web_sys::WebGlContextAttributes;
+
+/**
+ * Attributes the WebGL context is created with, defaults match the browser defaults.
+ *
+ * With `preserve_drawing_buffer` off the browser clears the canvas after every frame is presented,
+ * so everything has to be redrawn each animation frame. Turn it on for incremental rendering,
+ * at some cost in performance.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ContextOptions {
+    pub alpha: bool,
+    pub depth: bool,
+    pub stencil: bool,
+    pub antialias: bool,
+    pub premultiplied_alpha: bool,
+    pub preserve_drawing_buffer: bool,
+}
+
+impl Default for ContextOptions {
+    fn default() -> Self {
+        ContextOptions {
+            alpha: true,
+            depth: true,
+            stencil: false,
+            antialias: true,
+            premultiplied_alpha: true,
+            preserve_drawing_buffer: false,
+        }
+    }
+}
+
+impl ContextOptions {
+    pub fn alpha(self, alpha: bool) -> ContextOptions {
+        ContextOptions { alpha: alpha, ..self }
+    }
+
+    pub fn depth(self, depth: bool) -> ContextOptions {
+        ContextOptions { depth: depth, ..self }
+    }
+
+    pub fn stencil(self, stencil: bool) -> ContextOptions {
+        ContextOptions { stencil: stencil, ..self }
+    }
+
+    pub fn antialias(self, antialias: bool) -> ContextOptions {
+        ContextOptions { antialias: antialias, ..self }
+    }
+
+    pub fn premultiplied_alpha(self, premultiplied_alpha: bool) -> ContextOptions {
+        ContextOptions { premultiplied_alpha: premultiplied_alpha, ..self }
+    }
+
+    pub fn preserve_drawing_buffer(self, preserve_drawing_buffer: bool) -> ContextOptions {
+        ContextOptions { preserve_drawing_buffer: preserve_drawing_buffer, ..self }
+    }
+
+    pub(super) fn to_attributes(&self) -> WebGlContextAttributes {
+        let mut attributes = WebGlContextAttributes::new();
+        attributes
+            .alpha(self.alpha)
+            .depth(self.depth)
+            .stencil(self.stencil)
+            .antialias(self.antialias)
+            .premultiplied_alpha(self.premultiplied_alpha)
+            .preserve_drawing_buffer(self.preserve_drawing_buffer);
+        return attributes;
+    }
+}
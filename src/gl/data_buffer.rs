@@ -1,4 +1,6 @@
 use std::rc::Rc;
+use std::cell::Cell;
+use std::cell::RefCell;
 use web_sys::{
     WebGlRenderingContext as Context,
     WebGlBuffer,
@@ -10,6 +12,10 @@ use num_enum::{
 
 use super::Gl;
 use super::settings::Settings;
+use super::error::GlError;
+use super::primitive::PrimitiveType;
+use super::pod::Pod;
+use super::pod::bytes_of;
 
 #[repr(u32)]
 #[derive(Clone, Copy, Debug, TryFromPrimitive, IntoPrimitive, PartialEq, Eq)]
@@ -31,18 +37,35 @@ pub enum BufferUsage {
 #[derive(Debug, Clone)]
 pub struct ArrayBufferData {
     pub(self) gl: Gl,
+    pub(self) id: u64,
     pub(self) handle: WebGlBuffer,
+    pub(self) len_bytes: Cell<usize>,
+    pub(self) disposed: Cell<bool>,
+    /**
+     * Buffers borrowed with `borrow_raw` are owned elsewhere and never deleted by the crate.
+     */
+    pub(self) owned: bool,
+    pub(self) label: RefCell<Option<String>>,
 }
 
 impl Drop for ArrayBufferData {
     fn drop(&mut self) {
-        self.gl.context().delete_buffer(Some(&self.handle));
+        if self.owned && !self.disposed.get() {
+            self.gl.context().delete_buffer(Some(&self.handle));
+        }
+        self.gl.track_resources(|counts| counts.buffers = counts.buffers.saturating_sub(1));
+        // Borrowed buffers aren't deleted, so GL doesn't unbind them itself
+        if let Ok(mut cache) = self.gl.settings_cache().try_borrow_mut() {
+            if cache.evict_dropped_array_buffer() {
+                self.gl.context().bind_buffer(Context::ARRAY_BUFFER, None);
+            }
+        }
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct ArrayBuffer {
-    pub(self) data: Rc<ArrayBufferData>
+    pub(super) data: Rc<ArrayBufferData>
 }
 
 impl PartialEq<ArrayBuffer> for ArrayBuffer {
@@ -54,33 +77,116 @@ impl PartialEq<ArrayBuffer> for ArrayBuffer {
 impl Eq for ArrayBuffer {}
 
 impl ArrayBuffer {
-    pub fn new<T: Sized>(gl: Gl, data: &[T], usage: BufferUsage) -> ArrayBuffer {
+    pub fn new<T: Pod>(gl: Gl, data: &[T], usage: BufferUsage) -> ArrayBuffer {
         let ref context = gl.context();
         let buffer = context.create_buffer().unwrap();
 
+        gl.track_resources(|counts| counts.buffers += 1);
         let result = ArrayBuffer {
             data: Rc::new(ArrayBufferData {
+                id: gl.next_id(),
                 gl: gl.clone(),
                 handle: buffer,
+                len_bytes: Cell::new(0),
+                disposed: Cell::new(false),
+                owned: true,
+                label: RefCell::new(None),
             })
         };
 
-        result.write(data, usage);
+        result.write(data, usage).unwrap();
 
         return result;
     }
 
+    /**
+     * Zero filled buffer of `len_bytes`, to be filled with `write_sub_data`.
+     */
+    pub fn with_capacity(gl: Gl, len_bytes: usize, usage: BufferUsage) -> ArrayBuffer {
+        ArrayBuffer::new(gl, &vec![0u8; len_bytes], usage)
+    }
+
+    /**
+     * Take ownership of a buffer created outside of the crate, it's deleted when the last clone drops.
+     */
+    pub fn from_raw(gl: Gl, handle: WebGlBuffer, len_bytes: usize) -> ArrayBuffer {
+        ArrayBuffer::wrap_raw(gl, handle, len_bytes, true)
+    }
+
+    /**
+     * Use a buffer owned outside of the crate, it's never deleted by the crate.
+     */
+    pub fn borrow_raw(gl: Gl, handle: WebGlBuffer, len_bytes: usize) -> ArrayBuffer {
+        ArrayBuffer::wrap_raw(gl, handle, len_bytes, false)
+    }
+
+    pub(self) fn wrap_raw(gl: Gl, handle: WebGlBuffer, len_bytes: usize, owned: bool) -> ArrayBuffer {
+        gl.track_resources(|counts| counts.buffers += 1);
+        ArrayBuffer {
+            data: Rc::new(ArrayBufferData {
+                id: gl.next_id(),
+                gl: gl,
+                handle: handle,
+                len_bytes: Cell::new(len_bytes),
+                disposed: Cell::new(false),
+                owned: owned,
+                label: RefCell::new(None),
+            })
+        }
+    }
+
     pub(super) fn handle(&self) -> WebGlBuffer {
         self.data.handle.clone()
     }
 
-    pub fn write<T: Sized>(&self, data: &[T], usage: BufferUsage) {
+    pub fn gl(&self) -> Gl {
+        self.data.gl.clone()
+    }
+
+    pub fn len_bytes(&self) -> usize {
+        self.data.len_bytes.get()
+    }
+
+    /**
+     * Id increasing in creation order, a cheap key for sorting draws by buffer.
+     */
+    pub fn id(&self) -> u64 {
+        self.data.id
+    }
+
+    pub fn label(&self) -> Option<String> {
+        self.data.label.borrow().clone()
+    }
+
+    /**
+     * Name shown in `Debug` output, to tell the vertex buffers of meshes apart.
+     */
+    pub fn set_label(&self, label: &str) {
+        *self.data.label.borrow_mut() = Some(label.to_string());
+    }
+
+    /**
+     * Delete the GL buffer right away instead of waiting for the last clone to drop.
+     * Any later write returns `GlError::Disposed`, binding it binds nothing.
+     */
+    pub fn dispose(&self) {
+        if !self.data.disposed.replace(true) && self.data.owned {
+            self.data.gl.context().delete_buffer(Some(&self.data.handle));
+        }
+    }
+
+    pub fn is_disposed(&self) -> bool {
+        self.data.disposed.get()
+    }
+
+    pub fn write<T: Pod>(&self, data: &[T], usage: BufferUsage) -> Result<(), GlError> {
+        if self.is_disposed() {
+            return Err(GlError::Disposed);
+        }
         self.data.gl.apply(
             Gl::settings().array_buffer(self.clone()),
             || {
-                let bytes = unsafe {
-                    std::slice::from_raw_parts(data as *const [T] as *const u8, std::mem::size_of_val(data))
-                };
+                let bytes = bytes_of(data);
                 self.data.gl.context().buffer_data_with_u8_array(
                     Context::ARRAY_BUFFER,
                     &bytes,
@@ -88,5 +194,256 @@ impl ArrayBuffer {
                 );
             }
         );
+        self.data.len_bytes.set(std::mem::size_of_val(data));
+        return Ok(());
+    }
+
+    /**
+     * Overwrite part of the buffer starting at `offset_bytes`, the size of the buffer doesn't change.
+     */
+    pub fn write_sub_data<T: Pod>(&self, offset_bytes: usize, data: &[T]) -> Result<(), GlError> {
+        if self.is_disposed() {
+            return Err(GlError::Disposed);
+        }
+        let len = std::mem::size_of_val(data);
+        if offset_bytes.checked_add(len).map_or(true, |end| end > self.len_bytes()) {
+            return Err(GlError::BufferRange {
+                offset: offset_bytes,
+                len: len,
+                available: self.len_bytes(),
+            });
+        }
+        self.data.gl.apply(
+            Gl::settings().array_buffer(self.clone()),
+            || {
+                self.data.gl.context().buffer_sub_data_with_i32_and_u8_array(
+                    Context::ARRAY_BUFFER,
+                    offset_bytes as i32,
+                    bytes_of(data),
+                );
+            }
+        );
+        return Ok(());
+    }
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, TryFromPrimitive, IntoPrimitive, PartialEq, Eq)]
+pub enum IndexType {
+    U8 = Context::UNSIGNED_BYTE,
+    U16 = Context::UNSIGNED_SHORT,
+    /**
+     * Requires `OES_element_index_uint` on WebGL 1.
+     */
+    U32 = Context::UNSIGNED_INT,
+}
+
+impl IndexType {
+    pub fn size_in_bytes(&self) -> u32 {
+        match self {
+            IndexType::U8 => 1,
+            IndexType::U16 => 2,
+            IndexType::U32 => 4,
+        }
+    }
+}
+
+/**
+ * Types that can be stored in an element buffer.
+ */
+pub trait IndexData: Pod {
+    const INDEX_TYPE: IndexType;
+}
+
+impl IndexData for u8 {
+    const INDEX_TYPE: IndexType = IndexType::U8;
+}
+
+impl IndexData for u16 {
+    const INDEX_TYPE: IndexType = IndexType::U16;
+}
+
+impl IndexData for u32 {
+    const INDEX_TYPE: IndexType = IndexType::U32;
+}
+
+#[derive(Debug)]
+pub struct ElementBufferData {
+    pub(self) gl: Gl,
+    pub(self) handle: WebGlBuffer,
+    pub(self) index_type: Cell<IndexType>,
+    pub(self) count: Cell<u32>,
+    pub(self) disposed: Cell<bool>,
+    pub(self) label: RefCell<Option<String>>,
+}
+
+impl Drop for ElementBufferData {
+    fn drop(&mut self) {
+        if !self.disposed.get() {
+            self.gl.context().delete_buffer(Some(&self.handle));
+        }
+        self.gl.track_resources(|counts| counts.buffers = counts.buffers.saturating_sub(1));
+        if let Ok(mut cache) = self.gl.settings_cache().try_borrow_mut() {
+            if cache.evict_dropped_element_buffer() {
+                self.gl.context().bind_buffer(Context::ELEMENT_ARRAY_BUFFER, None);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ElementBuffer {
+    pub(super) data: Rc<ElementBufferData>
+}
+
+impl PartialEq<ElementBuffer> for ElementBuffer {
+    fn eq(&self, other: &ElementBuffer) -> bool {
+        self.data.handle == other.data.handle
+    }
+}
+
+impl Eq for ElementBuffer {}
+
+impl ElementBuffer {
+    /**
+     * Fails with `ExtensionMissing` for `u32` indices when `Gl::supports_u32_indices` is false.
+     */
+    pub fn new<T: IndexData>(gl: Gl, data: &[T], usage: BufferUsage) -> Result<ElementBuffer, GlError> {
+        if T::INDEX_TYPE == IndexType::U32 && !gl.supports_u32_indices() {
+            return Err(GlError::ExtensionMissing("OES_element_index_uint"));
+        }
+        let buffer = gl.context().create_buffer().unwrap();
+
+        gl.track_resources(|counts| counts.buffers += 1);
+        let result = ElementBuffer {
+            data: Rc::new(ElementBufferData {
+                gl: gl.clone(),
+                handle: buffer,
+                index_type: Cell::new(T::INDEX_TYPE),
+                count: Cell::new(0),
+                disposed: Cell::new(false),
+                label: RefCell::new(None),
+            })
+        };
+
+        result.write(data, usage)?;
+
+        return Ok(result);
+    }
+
+    pub(super) fn handle(&self) -> WebGlBuffer {
+        self.data.handle.clone()
+    }
+
+    pub fn gl(&self) -> Gl {
+        self.data.gl.clone()
+    }
+
+    pub fn index_type(&self) -> IndexType {
+        self.data.index_type.get()
+    }
+
+    /**
+     * Number of indices in the buffer.
+     */
+    pub fn count(&self) -> u32 {
+        self.data.count.get()
+    }
+
+    pub fn label(&self) -> Option<String> {
+        self.data.label.borrow().clone()
+    }
+
+    /**
+     * Name shown in `Debug` output, e.g. the mesh the indices belong to.
+     */
+    pub fn set_label(&self, label: &str) {
+        *self.data.label.borrow_mut() = Some(label.to_string());
+    }
+
+    pub fn dispose(&self) {
+        if !self.data.disposed.replace(true) {
+            self.data.gl.context().delete_buffer(Some(&self.data.handle));
+        }
+    }
+
+    pub fn is_disposed(&self) -> bool {
+        self.data.disposed.get()
+    }
+
+    pub fn write<T: IndexData>(&self, data: &[T], usage: BufferUsage) -> Result<(), GlError> {
+        if self.is_disposed() {
+            return Err(GlError::Disposed);
+        }
+        if T::INDEX_TYPE == IndexType::U32 && !self.data.gl.supports_u32_indices() {
+            return Err(GlError::ExtensionMissing("OES_element_index_uint"));
+        }
+        self.data.gl.apply(
+            Gl::settings().element_buffer(self.clone()),
+            || {
+                let bytes = bytes_of(data);
+                self.data.gl.context().buffer_data_with_u8_array(
+                    Context::ELEMENT_ARRAY_BUFFER,
+                    &bytes,
+                    usage.into(),
+                );
+            }
+        );
+        self.data.index_type.set(T::INDEX_TYPE);
+        self.data.count.set(data.len() as u32);
+        return Ok(());
+    }
+
+    /**
+     * Draw all the indices of the buffer with the currently applied program and attributes.
+     */
+    pub fn draw(&self, mode: PrimitiveType) {
+        self.draw_elements(mode, self.count(), 0);
     }
-}
\ No newline at end of file
+
+    /**
+     * Draw all the indices `instances` times, attributes with a non zero divisor advance per instance.
+     */
+    pub fn draw_instanced(&self, mode: PrimitiveType, instances: u32) {
+        let ref gl = self.data.gl;
+        gl.apply(
+            Gl::settings().element_buffer(self.clone()),
+            || {
+                gl.draw_elements_instanced(mode, self.count(), self.index_type(), 0, instances);
+            }
+        );
+    }
+
+    /**
+     * Draw `count` indices starting from the `offset_elements` index, for sub-meshes sharing the buffer.
+     * The offset is in elements, conversion to bytes is done according to the index type.
+     */
+    pub fn draw_elements_range(&self, mode: PrimitiveType, count: u32, offset_elements: u32) -> Result<(), GlError> {
+        if offset_elements.checked_add(count).map_or(true, |end| end > self.count()) {
+            return Err(GlError::IndexRange {
+                offset: offset_elements,
+                count: count,
+                available: self.count(),
+            });
+        }
+        self.draw_elements(mode, count, offset_elements);
+        return Ok(());
+    }
+
+    pub(self) fn draw_elements(&self, mode: PrimitiveType, count: u32, offset_elements: u32) {
+        let ref gl = self.data.gl;
+        gl.check_feedback_loop();
+        let index_type = self.index_type();
+        gl.apply(
+            Gl::settings().element_buffer(self.clone()),
+            || {
+                gl.context().draw_elements_with_i32(
+                    mode.into(),
+                    count as i32,
+                    index_type.into(),
+                    (offset_elements * index_type.size_in_bytes()) as i32,
+                );
+            }
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use super::gl::Gl;
+use super::settings::Settings;
+use super::program::Program;
+use super::texture::Texture;
+
+pub(self) struct QueuedDraw {
+    program: Program,
+    program_rank: usize,
+    textures: Vec<(u32, Texture)>,
+    textures_rank: usize,
+    blend: bool,
+    draw: Box<dyn FnOnce(&Gl)>,
+}
+
+/**
+ * Collects draws and issues them grouped by program, then textures, then blending, so each of them
+ * is changed once per group instead of once per draw. Settings specific to a draw are applied around
+ * its command only. Groups keep the order in which their first draw was pushed, draws within a group
+ * keep the push order, so the queue is only meant for draws that may be reordered, e.g. opaque geometry.
+ */
+pub struct DrawQueue {
+    pub(self) gl: Gl,
+    pub(self) draws: Vec<QueuedDraw>,
+    pub(self) programs: Vec<Program>,
+    pub(self) texture_sets: Vec<Vec<(u32, Texture)>>,
+}
+
+impl std::fmt::Debug for DrawQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("DrawQueue")
+            .field("draws", &self.draws.len())
+            .finish()
+    }
+}
+
+impl DrawQueue {
+    pub fn new(gl: Gl) -> DrawQueue {
+        DrawQueue {
+            gl: gl,
+            draws: Vec::new(),
+            programs: Vec::new(),
+            texture_sets: Vec::new(),
+        }
+    }
+
+    pub fn gl(&self) -> Gl {
+        self.gl.clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.draws.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.draws.is_empty()
+    }
+
+    /**
+     * Queue a draw with the program, `(unit, texture)` bindings and blending it needs. The rest of
+     * its state like attributes or blend function goes to `settings`.
+     */
+    pub fn push<S: Settings + 'static, F: FnOnce(&Gl) + 'static>(
+        &mut self,
+        program: Program,
+        textures: Vec<(u32, Texture)>,
+        blend: bool,
+        settings: S,
+        draw: F,
+    ) {
+        let program_rank = match self.programs.iter().position(|known| *known == program) {
+            Some(rank) => rank,
+            None => {
+                self.programs.push(program.clone());
+                self.programs.len() - 1
+            },
+        };
+        let textures_rank = match self.texture_sets.iter().position(|known| *known == textures) {
+            Some(rank) => rank,
+            None => {
+                self.texture_sets.push(textures.clone());
+                self.texture_sets.len() - 1
+            },
+        };
+        self.draws.push(QueuedDraw {
+            program: program,
+            program_rank: program_rank,
+            textures: textures,
+            textures_rank: textures_rank,
+            blend: blend,
+            draw: Box::new(move |gl: &Gl| gl.apply(settings, || draw(gl))),
+        });
+    }
+
+    /**
+     * Issue the queued draws and empty the queue.
+     */
+    pub fn flush(&mut self) {
+        let mut draws = std::mem::replace(&mut self.draws, Vec::new());
+        self.programs.clear();
+        self.texture_sets.clear();
+        // Stable sort, draws with the same state keep their push order
+        draws.sort_by_key(|draw| (draw.program_rank, draw.textures_rank, draw.blend));
+
+        let ref gl = self.gl;
+        for program_run in DrawQueue::split_runs(draws, |draw| draw.program_rank) {
+            let program = program_run[0].program.clone();
+            gl.apply(Gl::settings().program(program), || {
+                for textures_run in DrawQueue::split_runs(program_run, |draw| draw.textures_rank) {
+                    let textures = textures_run[0].textures.clone();
+                    gl.apply(Gl::settings().textures(textures), || {
+                        for blend_run in DrawQueue::split_runs(textures_run, |draw| draw.blend) {
+                            let blend = blend_run[0].blend;
+                            gl.apply(Gl::settings().blend(blend), || {
+                                for draw in blend_run {
+                                    (draw.draw)(gl);
+                                }
+                            });
+                        }
+                    });
+                }
+            });
+        }
+    }
+
+    /**
+     * Split sorted draws into runs with the same key.
+     */
+    pub(self) fn split_runs<K: PartialEq, F: Fn(&QueuedDraw) -> K>(draws: Vec<QueuedDraw>, key: F) -> Vec<Vec<QueuedDraw>> {
+        let mut runs: Vec<Vec<QueuedDraw>> = Vec::new();
+        for draw in draws {
+            let same = runs.last().map_or(false, |run| key(&run[0]) == key(&draw));
+            if same {
+                runs.last_mut().unwrap().push(draw);
+            } else {
+                runs.push(vec![draw]);
+            }
+        }
+        return runs;
+    }
+}
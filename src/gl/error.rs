@@ -0,0 +1,226 @@
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GlError {
+    /**
+     * Mip level dimensions don't match the base level size shifted by the level index.
+     */
+    InvalidMipLevel {
+        level: u32,
+        expected: (u32, u32),
+        actual: (u32, u32),
+    },
+    /**
+     * Mip level index is past the last level of the texture size.
+     */
+    MipLevelOutOfRange {
+        level: u32,
+        count: u32,
+    },
+    /**
+     * Base mip level of a range is above its max level, the texture would be incomplete.
+     */
+    InvalidMipRange {
+        base: u32,
+        max: u32,
+    },
+    /**
+     * Level of detail range has a minimum above its maximum or a NaN bound.
+     */
+    InvalidLodRange,
+    /**
+     * Layer index is past the last layer of the texture array.
+     */
+    LayerOutOfRange {
+        layer: u32,
+        layers: u32,
+    },
+    /**
+     * Rectangle of a sub image upload reaches past the texture size.
+     */
+    SubImageRange {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        size: (u32, u32),
+    },
+    /**
+     * Uploaded data length doesn't match the texture size.
+     */
+    DataSize {
+        expected: usize,
+        actual: usize,
+    },
+    /**
+     * The GL object was released with `dispose` and can't be used anymore.
+     */
+    Disposed,
+    /**
+     * A required WebGL extension isn't available in this context.
+     */
+    ExtensionMissing(&'static str),
+    /**
+     * The GPU timer was disjoint during the query, its result is meaningless.
+     */
+    TimerDisjoint,
+    /**
+     * The texture has non power of two size, WebGL 1 doesn't allow this parameter or operation for it.
+     */
+    NonPowerOfTwo {
+        width: u32,
+        height: u32,
+    },
+    /**
+     * The functionality is only available with a WebGL 2 context.
+     */
+    WebGl2Required,
+    /**
+     * Image couldn't be loaded from the url.
+     */
+    ImageLoad(String),
+    /**
+     * Shader compilation failed, contains the shader info log.
+     */
+    ShaderCompile(String),
+    /**
+     * Program linking failed, contains the program info log.
+     */
+    ProgramLink(String),
+    /**
+     * Vertex shader declares a sampler but the hardware has no vertex texture units.
+     */
+    VertexTexturesUnsupported,
+    /**
+     * Shader source includes a name missing from the includes map.
+     */
+    IncludeNotFound(String),
+    /**
+     * Shader includes form a cycle, contains the include chain.
+     */
+    IncludeCycle(String),
+    /**
+     * Framebuffer attachments are not renderable together, contains the framebuffer status.
+     */
+    FramebufferIncomplete(u32),
+    /**
+     * The same texture is used as an attachment being rendered to and as a texture being read.
+     */
+    FeedbackLoop,
+    /**
+     * Requested range of indices exceeds the element buffer.
+     */
+    IndexRange {
+        offset: u32,
+        count: u32,
+        available: u32,
+    },
+    /**
+     * Requested byte range exceeds the array buffer.
+     */
+    BufferRange {
+        offset: usize,
+        len: usize,
+        available: usize,
+    },
+    /**
+     * The program has no active attribute with the name, contains the name.
+     */
+    AttributeMissing(String),
+    /**
+     * The context belongs to a canvas the crate can't use, e.g. an `OffscreenCanvas`.
+     */
+    UnsupportedCanvas,
+    /**
+     * The browser doesn't implement the requested WebGL version.
+     */
+    WebGlUnsupported,
+    /**
+     * WebGL is implemented but the canvas returned no context, usually because it already has
+     * a context of another type, e.g. `2d`. Browsers blocking WebGL on the GPU report this too.
+     */
+    ContextInUse,
+    /**
+     * More textures are bound at once than the context has texture units.
+     */
+    TextureUnits {
+        required: u32,
+        available: u32,
+    },
+}
+
+impl fmt::Display for GlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GlError::InvalidMipLevel { level, expected, actual } => write!(
+                f,
+                "mip level {} must be {}x{}, got {}x{}",
+                level, expected.0, expected.1, actual.0, actual.1
+            ),
+            GlError::MipLevelOutOfRange { level, count } => write!(
+                f,
+                "mip level {} is out of range, the texture has {} levels",
+                level, count
+            ),
+            GlError::InvalidMipRange { base, max } => write!(
+                f,
+                "mip range base level {} is above max level {}",
+                base, max
+            ),
+            GlError::InvalidLodRange => write!(f, "level of detail range must have min <= max and no NaN"),
+            GlError::LayerOutOfRange { layer, layers } => write!(
+                f,
+                "layer {} is out of range, the texture array has {} layers",
+                layer, layers
+            ),
+            GlError::SubImageRange { x, y, width, height, size } => write!(
+                f,
+                "{}x{} rectangle at {},{} is out of the {}x{} texture",
+                width, height, x, y, size.0, size.1
+            ),
+            GlError::DataSize { expected, actual } => write!(
+                f,
+                "data has {} values, expected {}",
+                actual, expected
+            ),
+            GlError::Disposed => write!(f, "GL object is already disposed"),
+            GlError::ExtensionMissing(name) => write!(f, "extension {} is not available", name),
+            GlError::TimerDisjoint => write!(f, "GPU timer was disjoint, timing is invalid"),
+            GlError::NonPowerOfTwo { width, height } => write!(
+                f,
+                "texture {}x{} is not power of two, WebGL 1 only allows clamp to edge wrap and no mipmaps",
+                width, height
+            ),
+            GlError::WebGl2Required => write!(f, "WebGL 2 context is required"),
+            GlError::ImageLoad(url) => write!(f, "failed to load image {}", url),
+            GlError::ShaderCompile(log) => write!(f, "shader compilation failed: {}", log),
+            GlError::ProgramLink(log) => write!(f, "program linking failed: {}", log),
+            GlError::VertexTexturesUnsupported => write!(f, "vertex shader uses a sampler, vertex texture units are not available"),
+            GlError::IncludeNotFound(name) => write!(f, "shader include \"{}\" not found", name),
+            GlError::IncludeCycle(chain) => write!(f, "shader include cycle: {}", chain),
+            GlError::FramebufferIncomplete(status) => write!(f, "framebuffer is incomplete, status 0x{:x}", status),
+            GlError::FeedbackLoop => write!(f, "texture is read and rendered to at the same time"),
+            GlError::IndexRange { offset, count, available } => write!(
+                f,
+                "indices {}..{} are out of element buffer with {} indices",
+                offset, *offset as u64 + *count as u64, available
+            ),
+            GlError::BufferRange { offset, len, available } => write!(
+                f,
+                "bytes {}..{} are out of array buffer with {} bytes",
+                offset, *offset as u64 + *len as u64, available
+            ),
+            GlError::AttributeMissing(name) => write!(f, "program has no active attribute \"{}\"", name),
+            GlError::UnsupportedCanvas => write!(f, "context doesn't belong to an HtmlCanvasElement"),
+            GlError::WebGlUnsupported => write!(f, "WebGL is not supported by the browser"),
+            GlError::ContextInUse => write!(f, "canvas has a context of another type or WebGL is blocked"),
+            GlError::TextureUnits { required, available } => write!(
+                f,
+                "{} texture units are required, the context has {}",
+                required, available
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GlError {}
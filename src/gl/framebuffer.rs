@@ -0,0 +1,144 @@
+use std::rc::Rc;
+use std::cell::Cell;
+use std::cell::RefCell;
+use web_sys::{
+    WebGlFramebuffer,
+    WebGlRenderingContext as Context,
+};
+
+use super::gl::Gl;
+use super::error::GlError;
+use super::settings::Settings;
+use super::texture::Texture;
+use super::renderbuffer::Renderbuffer;
+
+#[derive(Debug)]
+pub struct FramebufferInfo {
+    pub(self) gl: Gl,
+    pub(super) handle: WebGlFramebuffer,
+    pub(self) color: Texture,
+    /**
+     * Attached renderbuffers are kept alive as long as the framebuffer.
+     */
+    pub(self) renderbuffers: RefCell<Vec<Renderbuffer>>,
+    pub(super) disposed: Cell<bool>,
+}
+
+impl Drop for FramebufferInfo {
+    fn drop(&mut self) {
+        if !self.disposed.get() {
+            self.gl.context().delete_framebuffer(Some(&self.handle));
+        }
+        self.gl.track_resources(|counts| counts.framebuffers = counts.framebuffers.saturating_sub(1));
+        // Rendering into a deleted framebuffer fails, fall back to the canvas if it was the bound one
+        if let Ok(mut cache) = self.gl.settings_cache().try_borrow_mut() {
+            if cache.evict_dropped_framebuffer() {
+                self.gl.context().bind_framebuffer(Context::FRAMEBUFFER, None);
+            }
+        }
+    }
+}
+
+/**
+ * Render target with a texture as color attachment. Bind it with `Gl::settings().framebuffer(...)`.
+ */
+#[derive(Clone, Debug)]
+pub struct Framebuffer {
+    pub(super) data: Rc<FramebufferInfo>,
+}
+
+impl PartialEq<Framebuffer> for Framebuffer {
+    fn eq(&self, other: &Framebuffer) -> bool {
+        self.data.handle == other.data.handle
+    }
+}
+
+impl Eq for Framebuffer {}
+
+impl Framebuffer {
+    pub fn new(gl: Gl, color: Texture) -> Result<Framebuffer, GlError> {
+        let handle = gl.context().create_framebuffer().unwrap();
+        gl.track_resources(|counts| counts.framebuffers += 1);
+        let result = Framebuffer {
+            data: Rc::new(FramebufferInfo {
+                gl: gl.clone(),
+                handle: handle,
+                color: color.clone(),
+                renderbuffers: RefCell::new(Vec::new()),
+                disposed: Cell::new(false),
+            }),
+        };
+
+        let status = gl.apply(
+            Gl::settings().framebuffer(result.clone()),
+            || {
+                gl.context().framebuffer_texture_2d(
+                    Context::FRAMEBUFFER,
+                    Context::COLOR_ATTACHMENT0,
+                    Context::TEXTURE_2D,
+                    Some(&color.data.handle),
+                    0,
+                );
+                gl.context().check_framebuffer_status(Context::FRAMEBUFFER)
+            }
+        );
+
+        if status != Context::FRAMEBUFFER_COMPLETE {
+            return Err(GlError::FramebufferIncomplete(status));
+        }
+        return Ok(result);
+    }
+
+    /**
+     * Attach the renderbuffer at the attachment point of its format. Color formats replace the color texture
+     * as the render destination, `color()` still returns the texture but it's no longer written to.
+     */
+    pub fn attach_renderbuffer(&self, renderbuffer: Renderbuffer) -> Result<(), GlError> {
+        if self.is_disposed() {
+            return Err(GlError::Disposed);
+        }
+        let ref gl = self.data.gl;
+        let status = gl.apply(
+            Gl::settings().framebuffer(self.clone()),
+            || {
+                gl.context().framebuffer_renderbuffer(
+                    Context::FRAMEBUFFER,
+                    renderbuffer.format().attachment(),
+                    Context::RENDERBUFFER,
+                    Some(&renderbuffer.data.handle),
+                );
+                gl.context().check_framebuffer_status(Context::FRAMEBUFFER)
+            }
+        );
+        let mut renderbuffers = self.data.renderbuffers.borrow_mut();
+        renderbuffers.retain(|attached| attached.format().attachment() != renderbuffer.format().attachment());
+        renderbuffers.push(renderbuffer);
+
+        if status != Context::FRAMEBUFFER_COMPLETE {
+            return Err(GlError::FramebufferIncomplete(status));
+        }
+        return Ok(());
+    }
+
+    pub fn gl(&self) -> Gl {
+        self.data.gl.clone()
+    }
+
+    pub fn color(&self) -> Texture {
+        self.data.color.clone()
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.data.color.size()
+    }
+
+    pub fn dispose(&self) {
+        if !self.data.disposed.replace(true) {
+            self.data.gl.context().delete_framebuffer(Some(&self.data.handle));
+        }
+    }
+
+    pub fn is_disposed(&self) -> bool {
+        self.data.disposed.get()
+    }
+}
@@ -1,49 +1,559 @@
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
+use wasm_bindgen::Clamped;
 use std::rc::Rc;
+use std::cell::Cell;
 use std::cell::RefCell;
+use js_sys::Int32Array;
 use web_sys::{
     WebGlRenderingContext as Context,
+    WebGl2RenderingContext as Context2,
     HtmlCanvasElement,
+    ImageData,
+    WebGlBuffer,
+    WebGlFramebuffer,
     AngleInstancedArrays,
+    ExtDisjointTimerQuery,
+    console,
 };
 
 use super::settings::Settings;
+use super::settings::DynSettings;
+use super::settings::apply_dyn_from;
 use super::settings::EmptySetting;
 use super::settings::SettingsCache;
+use super::settings::CachedSettings;
+use super::settings::ActiveTextureSetting;
+use super::settings::ColorspaceConversionSetting;
+use super::settings::MipmapHintSetting;
+use super::settings::ViewportSetting;
+use super::settings::Quality;
+use super::settings::FramebufferSetting;
+use super::settings::CompareFunction;
+use super::settings::StencilOp;
+use super::query::TimerQuery;
+use super::program::ShaderType;
+use super::program::PrecisionType;
+use super::program::PrecisionFormat;
+use super::program::GlslVersion;
+use super::program::Program;
+use super::program::ProgramCache;
+use super::error::GlError;
+use super::context_options::ContextOptions;
+use super::color::Color;
+use super::primitive::PrimitiveType;
+use super::data_buffer::IndexType;
+use super::data_buffer::ElementBuffer;
+use super::data_buffer::ArrayBuffer;
+use super::data_buffer::BufferUsage;
+use super::settings::AttributeSetting;
+use super::attribute::AttributeLayout;
+use super::texture::Texture;
+use super::texture::TextureFilter;
+use super::framebuffer::Framebuffer;
+
+#[wasm_bindgen]
+extern "C" {
+    /**
+     * `WEBGL_multi_draw` extension, web-sys doesn't bind it.
+     */
+    #[derive(Debug, Clone)]
+    pub(super) type WebGlMultiDraw;
+
+    #[wasm_bindgen(method, js_name = multiDrawElementsWEBGL)]
+    pub(super) fn multi_draw_elements_webgl(
+        this: &WebGlMultiDraw,
+        mode: u32,
+        counts: &[i32],
+        counts_offset: u32,
+        type_: u32,
+        offsets: &[i32],
+        offsets_offset: u32,
+        draw_count: i32,
+    );
+}
 
 #[derive(Debug)]
 pub(self) struct GlInfo {
+    pub(self) canvas: HtmlCanvasElement,
     pub(super) context: Context,
+    pub(super) context2: Option<Context2>,
     pub(self) settings_cache: RefCell<SettingsCache>,
-    pub(super) ex_instanced_arrays: AngleInstancedArrays,
+    pub(self) program_cache: RefCell<ProgramCache>,
+    pub(super) ex_instanced_arrays: Option<AngleInstancedArrays>,
+    pub(super) ex_timer_query: Option<ExtDisjointTimerQuery>,
+    /**
+     * `EXT_disjoint_timer_query_webgl2` is loaded, it only adds enums for the WebGL 2 query API.
+     */
+    pub(self) timer_query2: bool,
+    pub(super) ex_multi_draw: Option<WebGlMultiDraw>,
+    /**
+     * WebGL 2 or `OES_element_index_uint`, the extension only needs to be requested to take effect.
+     */
+    pub(self) u32_indices: bool,
+    pub(self) max_texture_units: u32,
+    pub(self) max_viewport_dims: (u32, u32),
+    pub(self) resource_counts: Cell<ResourceCounts>,
+    pub(self) debug: Cell<bool>,
+    pub(self) explicit_unbind: Cell<bool>,
+    pub(self) next_id: Cell<u64>,
+    /**
+     * Buffer of vertex indices `0..len` for `draw_attributeless` on WebGL 1.
+     */
+    pub(self) vertex_ids: RefCell<Option<(WebGlBuffer, u32)>>,
+    /**
+     * Framebuffer reused by internal copies and readbacks, created on first use.
+     */
+    pub(self) scratch_framebuffer: RefCell<Option<WebGlFramebuffer>>,
+}
+
+impl Drop for GlInfo {
+    fn drop(&mut self) {
+        if let Some((buffer, _)) = self.vertex_ids.borrow_mut().take() {
+            self.context.delete_buffer(Some(&buffer));
+        }
+        if let Some(framebuffer) = self.scratch_framebuffer.borrow_mut().take() {
+            self.context.delete_framebuffer(Some(&framebuffer));
+        }
+    }
+}
+
+/**
+ * Vertex attribute configuration as GL sees it, see `Gl::debug_attrib_state`.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttribDebugInfo {
+    pub enabled: bool,
+    pub size: u32,
+    pub data_type: u32,
+    pub stride: u32,
+    pub normalized: bool,
+    pub buffer: Option<WebGlBuffer>,
+    pub array_buffer: Option<WebGlBuffer>,
+}
+
+/**
+ * Number of live GL objects created through the crate, for leak detection. Objects are counted
+ * until their last clone is dropped, clones held e.g. in closures or caches keep them alive.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ResourceCounts {
+    pub textures: usize,
+    pub texture_arrays: usize,
+    pub buffers: usize,
+    pub programs: usize,
+    pub framebuffers: usize,
+}
+
+/**
+ * Texture units available to shaders, vertex texture fetch is often unsupported on older hardware.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TextureUnitLimits {
+    /**
+     * `MAX_COMBINED_TEXTURE_IMAGE_UNITS`, units that can have a texture bound.
+     */
+    pub combined: u32,
+    /**
+     * `MAX_TEXTURE_IMAGE_UNITS`, samplers usable in a fragment shader.
+     */
+    pub fragment: u32,
+    /**
+     * `MAX_VERTEX_TEXTURE_IMAGE_UNITS`, samplers usable in a vertex shader, may be 0.
+     */
+    pub vertex: u32,
 }
 
+/**
+ * Implementation limits of the context, exceeding them usually doesn't fail but is clamped or ignored by GL.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Limits {
+    pub texture_units: TextureUnitLimits,
+    /**
+     * `MAX_VERTEX_ATTRIBS`
+     */
+    pub max_vertex_attribs: u32,
+    /**
+     * `MAX_VIEWPORT_DIMS` as width and height.
+     */
+    pub max_viewport_dims: (u32, u32),
+}
+
+/**
+ * Optional functionality, core in WebGL 2 unless noted, behind extensions in WebGL 1. See `Gl::supports`.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Capability {
+    /**
+     * Sampling float textures, `OES_texture_float` on WebGL 1.
+     */
+    FloatTextures,
+    /**
+     * Linear filtering of float textures, `OES_texture_float_linear` on both versions.
+     */
+    FloatLinear,
+    /**
+     * Half float textures, `OES_texture_half_float` on WebGL 1.
+     */
+    HalfFloat,
+    /**
+     * Depth textures, `WEBGL_depth_texture` on WebGL 1.
+     */
+    DepthTexture,
+    /**
+     * Instanced draws, `ANGLE_instanced_arrays` on WebGL 1.
+     */
+    Instancing,
+    /**
+     * `u32` element indices, `OES_element_index_uint` on WebGL 1.
+     */
+    U32Indices,
+    /**
+     * sRGB textures and framebuffers, `EXT_sRGB` on WebGL 1.
+     */
+    Srgb,
+    /**
+     * Vertex array objects, `OES_vertex_array_object` on WebGL 1.
+     */
+    VertexArrayObjects,
+    /**
+     * Multiple render targets, `WEBGL_draw_buffers` on WebGL 1.
+     */
+    DrawBuffers,
+}
+
+/**
+ * Description of the hardware and browser behind the context, e.g. to attach to rendering bug reports.
+ * Serializable with the `serde` feature.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CapabilityReport {
+    pub webgl2: bool,
+    pub version: String,
+    pub shading_language_version: String,
+    /**
+     * Browsers usually report a generic vendor and renderer, the real ones are in `unmasked_*`.
+     */
+    pub vendor: String,
+    pub renderer: String,
+    /**
+     * GPU vendor from `WEBGL_debug_renderer_info`, `None` if the browser doesn't expose it.
+     */
+    pub unmasked_vendor: Option<String>,
+    pub unmasked_renderer: Option<String>,
+    pub limits: Limits,
+    pub extensions: Vec<String>,
+}
+
+/**
+ * WebGL context with cached state. It's bound to the thread owning the canvas and is intentionally not `Send`,
+ * see `ThreadBound`.
+ */
 #[derive(Clone, Debug)]
 pub struct Gl {
     pub(self) data: Rc<GlInfo>,
 }
 
 impl Gl {
-    pub(self) fn get_extension<Ex: JsCast>(context: &Context, name: &str) -> Ex {
-        context.get_extension(name).unwrap().unwrap().unchecked_into()
+    pub(self) fn get_optional_extension<Ex: JsCast>(context: &Context, name: &str) -> Option<Ex> {
+        context.get_extension(name).ok().and_then(|ex| ex).map(|ex| ex.unchecked_into())
+    }
+
+    pub(self) fn get_limit(context: &Context, name: u32) -> u32 {
+        context.get_parameter(name)
+            .ok()
+            .and_then(|value| value.as_f64())
+            .map_or(0, |value| value as u32)
+    }
+
+    pub(self) fn get_limit_pair(context: &Context, name: u32) -> (u32, u32) {
+        let mut values = [0; 2];
+        if let Some(limit) = context.get_parameter(name).ok()
+            .and_then(|value| value.dyn_into::<Int32Array>().ok())
+        {
+            limit.copy_to(&mut values);
+        }
+        return (values[0] as u32, values[1] as u32);
+    }
+
+    /**
+     * Get the context object of the canvas. The browser returns null both when it lacks the API
+     * and when the canvas already has a context of another type, the API presence tells them apart.
+     */
+    pub(self) fn get_context_object(
+        canvas: &HtmlCanvasElement,
+        context_id: &str,
+        constructor: &str,
+        options: &ContextOptions,
+    ) -> Result<JsValue, GlError> {
+        let context = canvas.get_context_with_context_options(context_id, &options.to_attributes())
+            .ok()
+            .and_then(|context| context);
+        return match context {
+            Some(context) => Ok(JsValue::from(context)),
+            None => {
+                let supported = js_sys::Reflect::has(&js_sys::global(), &constructor.into()).unwrap_or(false);
+                if supported {
+                    Err(GlError::ContextInUse)
+                } else {
+                    Err(GlError::WebGlUnsupported)
+                }
+            },
+        };
     }
 
-    pub fn new(canvas: &HtmlCanvasElement) -> Gl {
-        let context = Context::from(JsValue::from(canvas.get_context("webgl").unwrap().unwrap()));
-        Gl {
+    /**
+     * Create a WebGL 1 context, fails with `WebGlUnsupported` or `ContextInUse` when it can't be acquired.
+     */
+    pub fn new(canvas: &HtmlCanvasElement) -> Result<Gl, GlError> {
+        Gl::new_with_options(canvas, ContextOptions::default())
+    }
+
+    pub fn new_with_options(canvas: &HtmlCanvasElement, options: ContextOptions) -> Result<Gl, GlError> {
+        let context = Context::from(Gl::get_context_object(canvas, "webgl", "WebGLRenderingContext", &options)?);
+        let max_texture_units = Gl::get_limit(&context, Context::MAX_COMBINED_TEXTURE_IMAGE_UNITS);
+        let max_viewport_dims = Gl::get_limit_pair(&context, Context::MAX_VIEWPORT_DIMS);
+        Ok(Gl {
             data: Rc::new(GlInfo {
-                ex_instanced_arrays: Gl::get_extension(&context, "ANGLE_instanced_arrays"),
-                settings_cache: Default::default(),
+                ex_instanced_arrays: Gl::get_optional_extension(&context, "ANGLE_instanced_arrays"),
+                ex_timer_query: Gl::get_optional_extension(&context, "EXT_disjoint_timer_query"),
+                timer_query2: false,
+                ex_multi_draw: Gl::get_optional_extension(&context, "WEBGL_multi_draw"),
+                u32_indices: context.get_extension("OES_element_index_uint").ok().and_then(|ex| ex).is_some(),
+                settings_cache: RefCell::new(SettingsCache::for_context(&context, max_texture_units)),
+                program_cache: Default::default(),
+                canvas: canvas.clone(),
                 context: context,
+                context2: None,
+                max_texture_units: max_texture_units,
+                max_viewport_dims: max_viewport_dims,
+                resource_counts: Default::default(),
+                debug: Cell::new(false),
+                explicit_unbind: Cell::new(false),
+                next_id: Cell::new(1),
+                vertex_ids: RefCell::new(None),
+                scratch_framebuffer: RefCell::new(None),
             })
-        }
+        })
+    }
+
+    /**
+     * Create a WebGL 2 context. WebGL 1 API is still available through `context()`,
+     * WebGL 2 only functionality through `context2()`.
+     */
+    pub fn new_webgl2(canvas: &HtmlCanvasElement) -> Result<Gl, GlError> {
+        Gl::new_webgl2_with_options(canvas, ContextOptions::default())
+    }
+
+    pub fn new_webgl2_with_options(canvas: &HtmlCanvasElement, options: ContextOptions) -> Result<Gl, GlError> {
+        let context2 = Context2::from(Gl::get_context_object(canvas, "webgl2", "WebGL2RenderingContext", &options)?);
+        // WebGL 2 is a superset of WebGL 1, web-sys methods are looked up by name so the cast is safe
+        let context: Context = context2.clone().unchecked_into();
+        let max_texture_units = Gl::get_limit(&context, Context::MAX_COMBINED_TEXTURE_IMAGE_UNITS);
+        let max_viewport_dims = Gl::get_limit_pair(&context, Context::MAX_VIEWPORT_DIMS);
+        Ok(Gl {
+            data: Rc::new(GlInfo {
+                ex_instanced_arrays: None,
+                ex_timer_query: None,
+                timer_query2: context.get_extension("EXT_disjoint_timer_query_webgl2").ok().and_then(|ex| ex).is_some(),
+                ex_multi_draw: Gl::get_optional_extension(&context, "WEBGL_multi_draw"),
+                u32_indices: true,
+                settings_cache: RefCell::new(SettingsCache::for_context(&context, max_texture_units)),
+                program_cache: Default::default(),
+                canvas: canvas.clone(),
+                context: context,
+                context2: Some(context2),
+                max_texture_units: max_texture_units,
+                max_viewport_dims: max_viewport_dims,
+                resource_counts: Default::default(),
+                debug: Cell::new(false),
+                explicit_unbind: Cell::new(false),
+                next_id: Cell::new(1),
+                vertex_ids: RefCell::new(None),
+                scratch_framebuffer: RefCell::new(None),
+            })
+        })
+    }
+
+    /**
+     * Canvas the context was created for, e.g. to resize it or listen to its events.
+     */
+    pub fn canvas(&self) -> HtmlCanvasElement {
+        self.data.canvas.clone()
+    }
+
+    /**
+     * Wrap a context created elsewhere, e.g. by a framework owning the canvas. A WebGL 2 context
+     * passed as `WebGlRenderingContext` is recognized, extensions are loaded when available.
+     * Fails with `UnsupportedCanvas` if the context belongs to an `OffscreenCanvas`. The cache is initialized
+     * from the GL state, except for bound objects which the crate can't track.
+     */
+    pub fn from_context(context: Context) -> Result<Gl, GlError> {
+        let canvas: HtmlCanvasElement = context.canvas()
+            .and_then(|canvas| canvas.dyn_into().ok())
+            .ok_or(GlError::UnsupportedCanvas)?;
+        let context2: Option<Context2> = context.clone().dyn_into().ok();
+        let max_texture_units = Gl::get_limit(&context, Context::MAX_COMBINED_TEXTURE_IMAGE_UNITS);
+        let max_viewport_dims = Gl::get_limit_pair(&context, Context::MAX_VIEWPORT_DIMS);
+        let mut settings_cache = SettingsCache::for_context(&context, max_texture_units);
+        settings_cache.read_context_state(&context);
+        let (ex_instanced_arrays, ex_timer_query) = match context2 {
+            Some(_) => (None, None),
+            None => (
+                Gl::get_optional_extension(&context, "ANGLE_instanced_arrays"),
+                Gl::get_optional_extension(&context, "EXT_disjoint_timer_query"),
+            ),
+        };
+        return Ok(Gl {
+            data: Rc::new(GlInfo {
+                ex_instanced_arrays: ex_instanced_arrays,
+                ex_timer_query: ex_timer_query,
+                timer_query2: context2.is_some()
+                    && context.get_extension("EXT_disjoint_timer_query_webgl2").ok().and_then(|ex| ex).is_some(),
+                ex_multi_draw: Gl::get_optional_extension(&context, "WEBGL_multi_draw"),
+                u32_indices: context2.is_some()
+                    || context.get_extension("OES_element_index_uint").ok().and_then(|ex| ex).is_some(),
+                settings_cache: RefCell::new(settings_cache),
+                program_cache: Default::default(),
+                canvas: canvas,
+                context: context,
+                context2: context2,
+                max_texture_units: max_texture_units,
+                max_viewport_dims: max_viewport_dims,
+                resource_counts: Default::default(),
+                debug: Cell::new(false),
+                explicit_unbind: Cell::new(false),
+                next_id: Cell::new(1),
+                vertex_ids: RefCell::new(None),
+                scratch_framebuffer: RefCell::new(None),
+            })
+        });
     }
 
     pub fn context(&self) -> &Context {
         &self.data.context
     }
 
+    /**
+     * WebGL 2 context, `None` if the `Gl` was created with WebGL 1.
+     */
+    pub fn context2(&self) -> Option<&Context2> {
+        self.data.context2.as_ref()
+    }
+
+    pub fn is_webgl2(&self) -> bool {
+        self.data.context2.is_some()
+    }
+
+    /**
+     * Newest shading language version the context compiles.
+     */
+    pub fn glsl_version(&self) -> GlslVersion {
+        if self.is_webgl2() {
+            GlslVersion::Es300
+        } else {
+            GlslVersion::Es100
+        }
+    }
+
+    /**
+     * Whether the canvas keeps its content between frames. When it doesn't, everything must be redrawn
+     * on each animation frame, see `ContextOptions`.
+     */
+    pub fn preserves_drawing_buffer(&self) -> bool {
+        self.context().get_context_attributes()
+            .and_then(|attributes| js_sys::Reflect::get(&attributes, &"preserveDrawingBuffer".into()).ok())
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false)
+    }
+
+    pub(super) fn ex_timer_query(&self) -> Option<&ExtDisjointTimerQuery> {
+        self.data.ex_timer_query.as_ref()
+    }
+
+    /**
+     * WebGL 2 context if `EXT_disjoint_timer_query_webgl2` is loaded, timer queries then use its query API.
+     */
+    pub(super) fn timer_query_context2(&self) -> Option<&Context2> {
+        self.data.context2.as_ref().filter(|_| self.data.timer_query2)
+    }
+
+    pub(super) fn settings_cache(&self) -> &RefCell<SettingsCache> {
+        &self.data.settings_cache
+    }
+
+    pub(super) fn program_cache(&self) -> &RefCell<ProgramCache> {
+        &self.data.program_cache
+    }
+
+    /**
+     * Whether extra diagnostics are logged to the console, see `set_debug`.
+     */
+    pub fn debug(&self) -> bool {
+        self.data.debug.get()
+    }
+
+    /**
+     * Log warnings about usage that fails silently, like non power of two textures that WebGL 1
+     * samples as black. Off by default.
+     */
+    pub fn set_debug(&self, enabled: bool) {
+        self.data.debug.set(enabled);
+    }
+
+    pub fn explicit_unbind(&self) -> bool {
+        self.data.explicit_unbind.get()
+    }
+
+    /**
+     * Make scoped `apply` calls set and restore state even when the cache says it's already set, to measure
+     * what the cache saves on real hardware. Off by default.
+     */
+    pub fn set_explicit_unbind(&self, enabled: bool) {
+        self.data.explicit_unbind.set(enabled);
+    }
+
+    pub fn resource_counts(&self) -> ResourceCounts {
+        self.data.resource_counts.get()
+    }
+
+    pub(super) fn track_resources<F: FnOnce(&mut ResourceCounts)>(&self, update: F) {
+        let mut counts = self.data.resource_counts.get();
+        update(&mut counts);
+        self.data.resource_counts.set(counts);
+    }
+
+    /**
+     * Id for a new GL object, increasing in creation order and unique within the context.
+     */
+    pub(super) fn next_id(&self) -> u64 {
+        let id = self.data.next_id.get();
+        self.data.next_id.set(id + 1);
+        return id;
+    }
+
+    /**
+     * Whether both handles refer to the same context. GL objects only work in the context that created them.
+     */
+    pub fn is_same_context(&self, other: &Gl) -> bool {
+        Rc::ptr_eq(&self.data, &other.data)
+    }
+
+    /**
+     * Check that a resource created by `owner` can be used with this context, logging an error otherwise.
+     */
+    pub(super) fn check_owner(&self, owner: &Gl, kind: &str) -> bool {
+        if self.is_same_context(owner) {
+            return true;
+        }
+        console::error_1(&format!("{} belongs to a different Gl context, it is not bound", kind).into());
+        return false;
+    }
+
     pub fn settings() -> impl Settings {
         EmptySetting {}
     }
@@ -51,4 +561,626 @@ impl Gl {
     pub fn apply<R>(&self, settings: impl Settings, callback: impl FnOnce() -> R) -> R {
         settings.apply(self, &self.data.settings_cache, callback)
     }
+
+    /**
+     * Like `apply` for a list of settings only known at runtime, e.g. built from material data.
+     */
+    pub fn apply_all<R>(&self, settings: &[Box<dyn DynSettings>], callback: impl FnOnce() -> R) -> R {
+        let mut callback = Some(callback);
+        let mut result = None;
+        apply_dyn_from(settings, self, &self.data.settings_cache, &mut || {
+            if let Some(callback) = callback.take() {
+                result = Some(callback());
+            }
+        });
+        return result.unwrap();
+    }
+
+    /**
+     * Snapshot of the cached state, to be restored with `restore_state` after foreign code used the context.
+     */
+    pub fn save_state(&self) -> SettingsCache {
+        self.data.settings_cache.borrow().clone()
+    }
+
+    /**
+     * Make the snapshot the cached state and issue GL calls for all of it, whatever GL currently has.
+     * Objects dropped since the snapshot are restored as unbound.
+     */
+    pub fn restore_state(&self, state: SettingsCache) {
+        *self.data.settings_cache.borrow_mut() = state;
+        SettingsCache::write_all(self, &self.data.settings_cache);
+    }
+
+    /**
+     * Currently selected texture unit, 0-based.
+     */
+    pub fn active_texture(&self) -> u32 {
+        ActiveTextureSetting::get_cached(&self.data.settings_cache.borrow()).0
+    }
+
+    /**
+     * Select the texture unit (0-based, not `TEXTURE0 + unit`) outside of a scoped `apply`.
+     * Inside a scope that changes the active texture, it's restored when the scope exits.
+     */
+    pub fn set_active_texture(&self, unit: u32) {
+        let value = ActiveTextureSetting(unit);
+        let mut cache = self.data.settings_cache.borrow_mut();
+        if ActiveTextureSetting::get_cached(&cache) != value {
+            ActiveTextureSetting::set(self, &value);
+            ActiveTextureSetting::set_cached(&mut cache, &value);
+        }
+    }
+
+    /**
+     * Whether the browser converts the color space of uploaded images, on by default.
+     */
+    pub fn colorspace_conversion(&self) -> bool {
+        ColorspaceConversionSetting::get_cached(&self.data.settings_cache.borrow()).0
+    }
+
+    /**
+     * Enable or disable color space conversion of uploaded images outside of a scoped `apply`.
+     * Turn it off before uploading data textures like normal maps or masks.
+     */
+    pub fn set_colorspace_conversion(&self, enabled: bool) {
+        let value = ColorspaceConversionSetting(enabled);
+        let mut cache = self.data.settings_cache.borrow_mut();
+        if ColorspaceConversionSetting::get_cached(&cache) != value {
+            ColorspaceConversionSetting::set(self, &value);
+            ColorspaceConversionSetting::set_cached(&mut cache, &value);
+        }
+    }
+
+    pub fn mipmap_hint(&self) -> Quality {
+        MipmapHintSetting::get_cached(&self.data.settings_cache.borrow()).0
+    }
+
+    /**
+     * Quality of `Texture::generate_mipmap` outside of a scoped `apply`, `Nicest` for static textures
+     * like UI, `Fastest` for render targets regenerated every frame.
+     */
+    pub fn set_mipmap_hint(&self, quality: Quality) {
+        let value = MipmapHintSetting(quality);
+        let mut cache = self.data.settings_cache.borrow_mut();
+        if MipmapHintSetting::get_cached(&cache) != value {
+            MipmapHintSetting::set(self, &value);
+            MipmapHintSetting::set_cached(&mut cache, &value);
+        }
+    }
+
+    /**
+     * Precision of the shader type, useful to choose between `highp` and `mediump` before compiling.
+     */
+    pub fn shader_precision(&self, shader_type: ShaderType, precision_type: PrecisionType) -> PrecisionFormat {
+        self.context()
+            .get_shader_precision_format(shader_type.into(), precision_type.into())
+            .map(|format| PrecisionFormat {
+                range_min: format.range_min(),
+                range_max: format.range_max(),
+                precision: format.precision(),
+            })
+            .unwrap_or_default()
+    }
+
+    /**
+     * Return a live program compiled from the same sources, or compile a new one.
+     */
+    pub fn get_or_compile_program(&self, vertex_src: &str, fragment_src: &str) -> Result<Program, GlError> {
+        if let Some(program) = self.data.program_cache.borrow().get(vertex_src, fragment_src) {
+            return Ok(program);
+        }
+        let program = Program::new(self.clone(), vertex_src, fragment_src)?;
+        self.data.program_cache.borrow_mut().insert(vertex_src, fragment_src, &program);
+        return Ok(program);
+    }
+
+    /**
+     * Draw `count` vertices of the bound attributes. It's fine to have no attribute bound at all
+     * if the vertex shader generates the geometry, see `draw_attributeless`.
+     */
+    pub fn draw_arrays(&self, mode: PrimitiveType, first: u32, count: u32) {
+        self.check_feedback_loop();
+        self.context().draw_arrays(mode.into(), first as i32, count as i32);
+    }
+
+    /**
+     * Draw `count` vertices generated entirely in the vertex shader, e.g. a fullscreen triangle.
+     * WebGL 1 has no `gl_VertexID`, a program declaring `attribute float a_vertex_id;` gets the vertex index
+     * from a buffer managed by the crate. On WebGL 2 `gl_VertexID` works without it.
+     */
+    pub fn draw_attributeless(&self, program: &Program, mode: PrimitiveType, count: u32) {
+        let vertex_id = program.attribute_location("a_vertex_id").map(|location| {
+            AttributeSetting::new(location, self.vertex_ids(count), AttributeLayout::floats(1))
+        });
+        self.apply(
+            Gl::settings()
+                .program(program.clone())
+                .maybe(vertex_id),
+            || self.draw_arrays(mode, 0, count)
+        );
+    }
+
+    pub(self) fn vertex_ids(&self, count: u32) -> ArrayBuffer {
+        let mut vertex_ids = self.data.vertex_ids.borrow_mut();
+        if vertex_ids.as_ref().map_or(true, |(_, len)| *len < count) {
+            let buffer = match vertex_ids.take() {
+                Some((buffer, _)) => buffer,
+                None => self.context().create_buffer().unwrap(),
+            };
+            let ids: Vec<f32> = (0..count).map(|id| id as f32).collect();
+            ArrayBuffer::borrow_raw(self.clone(), buffer.clone(), 0).write(&ids, BufferUsage::Static).unwrap();
+            *vertex_ids = Some((buffer, count));
+        }
+        let (buffer, len) = vertex_ids.as_ref().unwrap();
+        // Borrowed so the buffer stays owned by the context, an owning clone here would be a cycle
+        return ArrayBuffer::borrow_raw(self.clone(), buffer.clone(), *len as usize * 4);
+    }
+
+    /**
+     * Draw `instances` copies of the vertices, attributes with a non zero divisor advance per instance.
+     */
+    pub fn draw_arrays_instanced(&self, mode: PrimitiveType, first: u32, count: u32, instances: u32) {
+        self.check_feedback_loop();
+        if let Some(context) = self.context2() {
+            context.draw_arrays_instanced(mode.into(), first as i32, count as i32, instances as i32);
+        } else if let Some(ex) = self.data.ex_instanced_arrays.as_ref() {
+            ex.draw_arrays_instanced_angle(mode.into(), first as i32, count as i32, instances as i32);
+        }
+    }
+
+    /**
+     * Draw several `(count, offset)` index ranges of the buffer, offsets are in elements like in
+     * `ElementBuffer::draw_elements_range`. The buffer is bound once, with `WEBGL_multi_draw`
+     * all ranges go in a single call.
+     */
+    pub fn multi_draw_elements(&self, mode: PrimitiveType, elements: &ElementBuffer, ranges: &[(u32, u32)]) -> Result<(), GlError> {
+        let available = elements.count();
+        for &(count, offset) in ranges {
+            if offset.checked_add(count).map_or(true, |end| end > available) {
+                return Err(GlError::IndexRange {
+                    offset: offset,
+                    count: count,
+                    available: available,
+                });
+            }
+        }
+
+        self.check_feedback_loop();
+        let index_type = elements.index_type();
+        self.apply(Gl::settings().element_buffer(elements.clone()), || {
+            if let Some(ex) = self.data.ex_multi_draw.as_ref() {
+                let counts: Vec<i32> = ranges.iter().map(|&(count, _)| count as i32).collect();
+                let offsets: Vec<i32> = ranges.iter()
+                    .map(|&(_, offset)| (offset * index_type.size_in_bytes()) as i32)
+                    .collect();
+                ex.multi_draw_elements_webgl(mode.into(), &counts, 0, index_type.into(), &offsets, 0, ranges.len() as i32);
+            } else {
+                for &(count, offset) in ranges {
+                    self.context().draw_elements_with_i32(
+                        mode.into(),
+                        count as i32,
+                        index_type.into(),
+                        (offset * index_type.size_in_bytes()) as i32,
+                    );
+                }
+            }
+        });
+        return Ok(());
+    }
+
+    pub(super) fn draw_elements_instanced(&self, mode: PrimitiveType, count: u32, index_type: IndexType, offset_bytes: u32, instances: u32) {
+        self.check_feedback_loop();
+        if let Some(context) = self.context2() {
+            context.draw_elements_instanced_with_i32(mode.into(), count as i32, index_type.into(), offset_bytes as i32, instances as i32);
+        } else if let Some(ex) = self.data.ex_instanced_arrays.as_ref() {
+            ex.draw_elements_instanced_angle_with_i32(mode.into(), count as i32, index_type.into(), offset_bytes as i32, instances as i32);
+        }
+    }
+
+    /**
+     * In debug mode, warn before a draw that samples the texture it renders into.
+     */
+    pub(super) fn check_feedback_loop(&self) {
+        if !self.debug() {
+            return;
+        }
+        let cache = self.data.settings_cache.borrow();
+        if let (Some(unit), Some(framebuffer)) = (cache.feedback_loop_unit(), cache.framebuffer()) {
+            console::warn_1(&format!(
+                "rwgl: {} is the render target and is bound to texture unit {}, sampling it while drawing is undefined",
+                framebuffer.color().describe(), unit
+            ).into());
+        }
+    }
+
+    pub(super) fn vertex_attrib_divisor(&self, location: u32, divisor: u32) {
+        if let Some(context) = self.context2() {
+            context.vertex_attrib_divisor(location, divisor);
+        } else if let Some(ex) = self.data.ex_instanced_arrays.as_ref() {
+            ex.vertex_attrib_divisor_angle(location, divisor);
+        }
+    }
+
+    /**
+     * Whether `ElementBuffer` accepts `u32` indices, always on WebGL 2 and with `OES_element_index_uint` on WebGL 1.
+     */
+    pub fn supports_u32_indices(&self) -> bool {
+        self.data.u32_indices
+    }
+
+    /**
+     * Whether the capability is available, requesting the extension it needs on WebGL 1 enables it.
+     */
+    pub fn supports(&self, capability: Capability) -> bool {
+        let has_extension = |name: &str| self.context().get_extension(name).ok().and_then(|ex| ex).is_some();
+        match capability {
+            Capability::FloatLinear => has_extension("OES_texture_float_linear"),
+            Capability::U32Indices => self.supports_u32_indices(),
+            _ if self.is_webgl2() => true,
+            Capability::FloatTextures => has_extension("OES_texture_float"),
+            Capability::HalfFloat => has_extension("OES_texture_half_float"),
+            Capability::DepthTexture => has_extension("WEBGL_depth_texture"),
+            Capability::Instancing => self.data.ex_instanced_arrays.is_some(),
+            Capability::Srgb => has_extension("EXT_sRGB"),
+            Capability::VertexArrayObjects => has_extension("OES_vertex_array_object"),
+            Capability::DrawBuffers => has_extension("WEBGL_draw_buffers"),
+        }
+    }
+
+    pub fn supports_timer_query(&self) -> bool {
+        self.data.ex_timer_query.is_some() || self.data.timer_query2
+    }
+
+    /**
+     * Measure GPU time spent on the commands issued by the callback. Timer queries can't be nested.
+     */
+    pub fn time_elapsed<R>(&self, callback: impl FnOnce() -> R) -> (R, TimerQuery) {
+        let query = TimerQuery::new(self.clone());
+        let result = query.measure(callback);
+        (result, query)
+    }
+
+    /**
+     * Draw a stencil mask with the first callback, then draw with the second one only where the mask was drawn.
+     * The stencil buffer is cleared to zero before the mask is drawn, the stencil state is restored afterwards.
+     */
+    pub fn stencil_mask<R>(&self, draw_mask: impl FnOnce(), draw_masked: impl FnOnce() -> R) -> R {
+        let mask_settings = Gl::settings()
+            .stencil_test(true)
+            .stencil_mask(!0)
+            .stencil_func(CompareFunction::Always, 1, !0)
+            .stencil_op(StencilOp::Keep, StencilOp::Keep, StencilOp::Replace)
+            .color_mask(false, false, false, false);
+        self.apply(mask_settings, || {
+            self.context().clear_stencil(0);
+            self.context().clear(Context::STENCIL_BUFFER_BIT);
+            draw_mask();
+        });
+
+        let masked_settings = Gl::settings()
+            .stencil_test(true)
+            .stencil_mask(0)
+            .stencil_func(CompareFunction::Equal, 1, !0)
+            .stencil_op(StencilOp::Keep, StencilOp::Keep, StencilOp::Keep);
+        return self.apply(masked_settings, draw_masked);
+    }
+
+    /**
+     * Read the real GL state of a vertex attribute, useful when a draw renders nothing.
+     * `array_buffer` is the buffer currently bound to `ARRAY_BUFFER`.
+     */
+    pub fn debug_attrib_state(&self, location: u32) -> AttribDebugInfo {
+        let ref context = self.data.context;
+        let parameter = |name: u32| context.get_vertex_attrib(location, name).unwrap_or(JsValue::NULL);
+        AttribDebugInfo {
+            enabled: parameter(Context::VERTEX_ATTRIB_ARRAY_ENABLED).as_bool().unwrap_or(false),
+            size: parameter(Context::VERTEX_ATTRIB_ARRAY_SIZE).as_f64().unwrap_or(0.0) as u32,
+            data_type: parameter(Context::VERTEX_ATTRIB_ARRAY_TYPE).as_f64().unwrap_or(0.0) as u32,
+            stride: parameter(Context::VERTEX_ATTRIB_ARRAY_STRIDE).as_f64().unwrap_or(0.0) as u32,
+            normalized: parameter(Context::VERTEX_ATTRIB_ARRAY_NORMALIZED).as_bool().unwrap_or(false),
+            buffer: parameter(Context::VERTEX_ATTRIB_ARRAY_BUFFER_BINDING).dyn_into().ok(),
+            array_buffer: context.get_parameter(Context::ARRAY_BUFFER_BINDING)
+                .ok()
+                .and_then(|buffer| buffer.dyn_into().ok()),
+        }
+    }
+
+    pub fn max_vertex_attribs(&self) -> u32 {
+        Gl::get_limit(self.context(), Context::MAX_VERTEX_ATTRIBS)
+    }
+
+    pub fn texture_unit_limits(&self) -> TextureUnitLimits {
+        TextureUnitLimits {
+            combined: self.data.max_texture_units,
+            fragment: Gl::get_limit(self.context(), Context::MAX_TEXTURE_IMAGE_UNITS),
+            vertex: Gl::get_limit(self.context(), Context::MAX_VERTEX_TEXTURE_IMAGE_UNITS),
+        }
+    }
+
+    /**
+     * `MAX_VIEWPORT_DIMS` as width and height, larger viewports are silently clamped by GL.
+     */
+    pub fn max_viewport_dims(&self) -> (u32, u32) {
+        self.data.max_viewport_dims
+    }
+
+    pub fn capability_report(&self) -> CapabilityReport {
+        // Constants of `WEBGL_debug_renderer_info`
+        const UNMASKED_VENDOR_WEBGL: u32 = 0x9245;
+        const UNMASKED_RENDERER_WEBGL: u32 = 0x9246;
+        let ref context = self.data.context;
+        let string = |name: u32| context.get_parameter(name).ok().and_then(|value| value.as_string());
+        let debug_info = context.get_extension("WEBGL_debug_renderer_info").ok().and_then(|ex| ex).is_some();
+        let extensions = context.get_supported_extensions()
+            .map(|extensions| extensions.iter().filter_map(|name| name.as_string()).collect())
+            .unwrap_or_default();
+        CapabilityReport {
+            webgl2: self.is_webgl2(),
+            version: string(Context::VERSION).unwrap_or_default(),
+            shading_language_version: string(Context::SHADING_LANGUAGE_VERSION).unwrap_or_default(),
+            vendor: string(Context::VENDOR).unwrap_or_default(),
+            renderer: string(Context::RENDERER).unwrap_or_default(),
+            unmasked_vendor: if debug_info { string(UNMASKED_VENDOR_WEBGL) } else { None },
+            unmasked_renderer: if debug_info { string(UNMASKED_RENDERER_WEBGL) } else { None },
+            limits: self.limits(),
+            extensions: extensions,
+        }
+    }
+
+    pub fn limits(&self) -> Limits {
+        Limits {
+            texture_units: self.texture_unit_limits(),
+            max_vertex_attribs: self.max_vertex_attribs(),
+            max_viewport_dims: self.max_viewport_dims(),
+        }
+    }
+
+    /**
+     * Number of texture units usable with `Settings::texture`, `MAX_COMBINED_TEXTURE_IMAGE_UNITS`.
+     */
+    pub fn max_texture_units(&self) -> u32 {
+        self.data.max_texture_units
+    }
+
+    /**
+     * Disable every vertex attribute array, so arrays left enabled for a previous program
+     * don't read out of range for the next one. Meant to be called outside of `apply` scopes.
+     */
+    pub fn disable_all_attributes(&self) {
+        for location in 0..self.max_vertex_attribs() {
+            self.context().disable_vertex_attrib_array(location);
+            self.vertex_attrib_divisor(location, 0);
+        }
+        self.data.settings_cache.borrow_mut().reset_attributes();
+    }
+
+    /**
+     * Disable the array of the attribute and read the constant value instead, so one program serves meshes
+     * with and without the attribute, e.g. per vertex and flat colors. Meant to be called outside of `apply`
+     * scopes binding the attribute, they enable the array again when they end.
+     */
+    pub fn set_attribute_constant(&self, location: u32, value: [f32; 4]) {
+        self.context().disable_vertex_attrib_array(location);
+        self.vertex_attrib_divisor(location, 0);
+        self.context().vertex_attrib4fv_with_f32_array(location, &value);
+        self.data.settings_cache.borrow_mut().reset_attribute(location);
+    }
+
+    /**
+     * Call `frame` on every animation frame with the seconds elapsed since the previous one (0 for the first),
+     * until it returns `false`.
+     */
+    pub fn run_animation_loop<F: FnMut(&Gl, f64) -> bool + 'static>(self, mut frame: F) {
+        let next: Rc<RefCell<Option<Closure<dyn FnMut(f64)>>>> = Rc::new(RefCell::new(None));
+        let request = next.clone();
+        let mut previous: Option<f64> = None;
+
+        *request.borrow_mut() = Some(Closure::wrap(Box::new(move |timestamp: f64| {
+            let delta = previous.map_or(0.0, |previous| (timestamp - previous) / 1000.0);
+            previous = Some(timestamp);
+            if frame(&self, delta) {
+                Gl::request_animation_frame(next.borrow().as_ref().unwrap());
+            } else {
+                // Break the closure's reference to itself so it's released, as the wasm-bindgen examples do
+                let _ = next.borrow_mut().take();
+            }
+        }) as Box<dyn FnMut(f64)>));
+
+        Gl::request_animation_frame(request.borrow().as_ref().unwrap());
+    }
+
+    pub(self) fn request_animation_frame(callback: &Closure<dyn FnMut(f64)>) {
+        web_sys::window()
+            .unwrap()
+            .request_animation_frame(callback.as_ref().unchecked_ref())
+            .unwrap();
+    }
+
+    /**
+     * Copy the color of `src` into `dst` scaled to its size, `Linear` for smooth downscaled thumbnails.
+     * WebGL 2 only. The cached framebuffer binding is restored afterwards.
+     */
+    pub fn blit(&self, src: &Framebuffer, dst: &Framebuffer, filter: TextureFilter) -> Result<(), GlError> {
+        let context2 = self.context2().ok_or(GlError::WebGl2Required)?;
+        if src.is_disposed() || dst.is_disposed() {
+            return Err(GlError::Disposed);
+        }
+        let (src_width, src_height) = src.size();
+        let (dst_width, dst_height) = dst.size();
+        context2.bind_framebuffer(Context2::READ_FRAMEBUFFER, Some(&src.data.handle));
+        context2.bind_framebuffer(Context2::DRAW_FRAMEBUFFER, Some(&dst.data.handle));
+        context2.blit_framebuffer(
+            0,
+            0,
+            src_width as i32,
+            src_height as i32,
+            0,
+            0,
+            dst_width as i32,
+            dst_height as i32,
+            Context::COLOR_BUFFER_BIT,
+            i32::from(filter) as u32,
+        );
+        // Binding `FRAMEBUFFER` sets both the read and the draw binding
+        let previous = self.settings_cache().borrow().framebuffer();
+        FramebufferSetting::set_framebuffer(self, previous.as_ref());
+        return Ok(());
+    }
+
+    /**
+     * Largest viewport with the aspect ratio (width / height) centered in the drawing buffer,
+     * leaving bars on the sides (pillarbox) or at the top and bottom (letterbox).
+     * Use it as a setting, e.g. `gl.apply(gl.fit_viewport(16.0 / 9.0), || ...)`.
+     */
+    pub fn fit_viewport(&self, target_aspect: f32) -> ViewportSetting {
+        Gl::fit_rect(self.context().drawing_buffer_width(), self.context().drawing_buffer_height(), target_aspect)
+    }
+
+    pub(self) fn fit_rect(width: i32, height: i32, target_aspect: f32) -> ViewportSetting {
+        if !(target_aspect > 0.0 && target_aspect.is_finite()) || width <= 0 || height <= 0 {
+            return ViewportSetting(0, 0, width, height);
+        }
+        if width as f32 > height as f32 * target_aspect {
+            let fit_width = ((height as f32 * target_aspect).round() as i32).max(1).min(width);
+            return ViewportSetting((width - fit_width) / 2, 0, fit_width, height);
+        } else {
+            let fit_height = ((width as f32 / target_aspect).round() as i32).max(1).min(height);
+            return ViewportSetting(0, (height - fit_height) / 2, width, fit_height);
+        }
+    }
+
+    /**
+     * Current viewport as `(x, y, width, height)`, seeded from GL when the context is created.
+     */
+    pub fn viewport(&self) -> (i32, i32, i32, i32) {
+        let ViewportSetting(x, y, width, height) = ViewportSetting::get_cached(&self.data.settings_cache.borrow());
+        return (x, y, width, height);
+    }
+
+    /**
+     * Read RGBA pixels of the bound framebuffer into `ImageData` for a 2D canvas. Rows are flipped,
+     * GL rows go bottom-up and canvas rows top-down. `None` if width or height is zero, e.g. for a collapsed
+     * canvas, `ImageData` can't be empty.
+     */
+    pub fn read_image_data(&self, x: i32, y: i32, width: u32, height: u32) -> Option<ImageData> {
+        if width == 0 || height == 0 {
+            return None;
+        }
+        let row_len = width as usize * 4;
+        let mut pixels = vec![0u8; row_len * height as usize];
+        self.context().read_pixels_with_opt_u8_array(
+            x,
+            y,
+            width as i32,
+            height as i32,
+            Context::RGBA,
+            Context::UNSIGNED_BYTE,
+            Some(&mut pixels),
+        ).unwrap();
+
+        let mut flipped = vec![0u8; pixels.len()];
+        for (row, source) in pixels.chunks(row_len).rev().enumerate() {
+            flipped[row * row_len..(row + 1) * row_len].copy_from_slice(source);
+        }
+        return Some(ImageData::new_with_u8_clamped_array_and_sh(Clamped(&mut flipped), width, height).unwrap());
+    }
+
+    /**
+     * Run the callback with the scratch framebuffer bound and the texture as its color attachment,
+     * for reading or copying from the texture without creating a framebuffer each time.
+     * The texture is detached and the cached framebuffer bound again afterwards. Fails with
+     * `FramebufferIncomplete` if the texture can't be a color attachment, the callback isn't run then.
+     */
+    pub(super) fn with_scratch_framebuffer<R, F: FnOnce() -> R>(&self, texture: &Texture, callback: F) -> Result<R, GlError> {
+        let ref context = self.data.context;
+        let framebuffer = self.data.scratch_framebuffer.borrow_mut()
+            .get_or_insert_with(|| context.create_framebuffer().unwrap())
+            .clone();
+        context.bind_framebuffer(Context::FRAMEBUFFER, Some(&framebuffer));
+        context.framebuffer_texture_2d(
+            Context::FRAMEBUFFER,
+            Context::COLOR_ATTACHMENT0,
+            Context::TEXTURE_2D,
+            Some(&texture.data.handle),
+            0,
+        );
+        let status = context.check_framebuffer_status(Context::FRAMEBUFFER);
+        let result = if status == Context::FRAMEBUFFER_COMPLETE {
+            Ok(callback())
+        } else {
+            Err(GlError::FramebufferIncomplete(status))
+        };
+        context.framebuffer_texture_2d(Context::FRAMEBUFFER, Context::COLOR_ATTACHMENT0, Context::TEXTURE_2D, None, 0);
+        let previous = self.settings_cache().borrow().framebuffer();
+        FramebufferSetting::set_framebuffer(self, previous.as_ref());
+        return result;
+    }
+
+    /**
+     * Clear the color of the bound framebuffer. On WebGL 2 the color is written as is with `clearBufferfv`,
+     * so float attachments (e.g. HDR accumulation) can be cleared to values outside 0..1, fixed-point ones
+     * are clamped by GL. WebGL 1 only has `clearColor`, which clamps to 0..1 even for float attachments.
+     */
+    pub fn clear<C: Into<Color>>(&self, color: C) {
+        let color = color.into();
+        if let Some(context2) = self.context2() {
+            let Color { red, green, blue, alpha } = color;
+            context2.clear_bufferfv_with_f32_array(Context2::COLOR, 0, &[red, green, blue, alpha]);
+            return;
+        }
+        self.apply(Gl::settings().clear_color(color), || {
+            self.context().clear(Context::COLOR_BUFFER_BIT);
+        });
+    }
+
+    /**
+     * Clear only the rectangle, in pixels from the bottom left corner.
+     */
+    pub fn clear_rect<C: Into<Color>>(&self, x: i32, y: i32, width: i32, height: i32, color: C) {
+        self.apply(Gl::settings().scissor(x, y, width, height), || self.clear(color));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit_rect_letterboxes_wider_aspect() {
+        assert_eq!(Gl::fit_rect(800, 800, 2.0), ViewportSetting(0, 200, 800, 400));
+    }
+
+    #[test]
+    fn fit_rect_pillarboxes_narrower_aspect() {
+        assert_eq!(Gl::fit_rect(800, 400, 1.0), ViewportSetting(200, 0, 400, 400));
+    }
+
+    #[test]
+    fn fit_rect_fills_matching_aspect() {
+        assert_eq!(Gl::fit_rect(1600, 900, 16.0 / 9.0), ViewportSetting(0, 0, 1600, 900));
+        assert_eq!(Gl::fit_rect(3, 3, 1.0), ViewportSetting(0, 0, 3, 3));
+    }
+
+    #[test]
+    fn fit_rect_rounds_and_centers_odd_sizes() {
+        // 33 * 1.5 = 49.5 rounds up
+        assert_eq!(Gl::fit_rect(100, 33, 1.5), ViewportSetting(25, 0, 50, 33));
+        // The odd leftover pixel goes to the right or top bar
+        assert_eq!(Gl::fit_rect(801, 400, 1.0), ViewportSetting(200, 0, 400, 400));
+        assert_eq!(Gl::fit_rect(400, 801, 1.0), ViewportSetting(0, 200, 400, 400));
+    }
+
+    #[test]
+    fn fit_rect_keeps_at_least_one_pixel() {
+        assert_eq!(Gl::fit_rect(1000, 1, 0.001), ViewportSetting(499, 0, 1, 1));
+    }
+
+    #[test]
+    fn fit_rect_passes_through_zero_size_and_invalid_aspect() {
+        assert_eq!(Gl::fit_rect(0, 0, 1.0), ViewportSetting(0, 0, 0, 0));
+        assert_eq!(Gl::fit_rect(0, 100, 1.0), ViewportSetting(0, 0, 0, 100));
+        assert_eq!(Gl::fit_rect(640, 480, 0.0), ViewportSetting(0, 0, 640, 480));
+        assert_eq!(Gl::fit_rect(640, 480, std::f32::NAN), ViewportSetting(0, 0, 640, 480));
+    }
 }
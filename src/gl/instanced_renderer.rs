@@ -0,0 +1,119 @@
+use std::marker::PhantomData;
+
+use super::gl::Gl;
+use super::settings::Settings;
+use super::settings::AttributeSetting;
+use super::attribute::VertexFormat;
+use super::data_buffer::ArrayBuffer;
+use super::data_buffer::ElementBuffer;
+use super::data_buffer::BufferUsage;
+use super::primitive::PrimitiveType;
+use super::program::Program;
+use super::error::GlError;
+use super::pod::Pod;
+
+/**
+ * Draws a base mesh once per instance of `T`, a `#[repr(C)]` struct described by the instance format,
+ * e.g. a `mat4` transform and a color.
+ *
+ * Vertex attributes read from the mesh buffer, instance attributes from the instance buffer with divisor 1.
+ * All instance attributes must be active in the program, vertex attributes the program doesn't use are skipped.
+ */
+#[derive(Debug)]
+pub struct InstancedRenderer<T: Pod> {
+    pub(self) gl: Gl,
+    pub(self) program: Program,
+    pub(self) vertices: ArrayBuffer,
+    pub(self) vertex_format: VertexFormat,
+    pub(self) elements: ElementBuffer,
+    pub(self) instances: ArrayBuffer,
+    pub(self) instance_format: VertexFormat,
+    pub(self) instance_count: u32,
+    pub(self) instance_type: PhantomData<T>,
+}
+
+impl<T: Pod> InstancedRenderer<T> {
+    pub fn new(
+        gl: Gl,
+        program: Program,
+        vertices: ArrayBuffer,
+        vertex_format: VertexFormat,
+        elements: ElementBuffer,
+        instance_format: VertexFormat,
+    ) -> Result<InstancedRenderer<T>, GlError> {
+        if std::mem::size_of::<T>() != instance_format.stride() as usize {
+            return Err(GlError::DataSize {
+                expected: instance_format.stride() as usize,
+                actual: std::mem::size_of::<T>(),
+            });
+        }
+        for (name, _) in instance_format.components() {
+            if program.attribute_location(&name).is_none() {
+                return Err(GlError::AttributeMissing(name));
+            }
+        }
+        let no_instances: [T; 0] = [];
+        return Ok(InstancedRenderer {
+            instances: ArrayBuffer::new(gl.clone(), &no_instances, BufferUsage::Dynamic),
+            gl: gl,
+            program: program,
+            vertices: vertices,
+            vertex_format: vertex_format,
+            elements: elements,
+            instance_format: instance_format,
+            instance_count: 0,
+            instance_type: PhantomData,
+        });
+    }
+
+    pub fn gl(&self) -> Gl {
+        self.gl.clone()
+    }
+
+    pub fn program(&self) -> Program {
+        self.program.clone()
+    }
+
+    pub fn instance_count(&self) -> u32 {
+        self.instance_count
+    }
+
+    /**
+     * Replace the instance data uploaded to the instance buffer.
+     */
+    pub fn update_instances(&mut self, instances: &[T]) -> Result<(), GlError> {
+        self.instances.write(instances, BufferUsage::Dynamic)?;
+        self.instance_count = instances.len() as u32;
+        return Ok(());
+    }
+
+    /**
+     * Draw the first `count` instances as triangles, uniforms are set by the caller beforehand.
+     */
+    pub fn draw(&self, count: u32) -> Result<(), GlError> {
+        if count > self.instance_count {
+            return Err(GlError::DataSize {
+                expected: count as usize,
+                actual: self.instance_count as usize,
+            });
+        }
+        if count == 0 {
+            return Ok(());
+        }
+        let mut attributes = self.vertex_format.attributes(&self.program, &self.vertices);
+        attributes.extend(
+            self.instance_format.components()
+                .into_iter()
+                .filter_map(|(name, layout)| self.program.attribute_location(&name).map(|location| {
+                    AttributeSetting::new(location, self.instances.clone(), layout.divisor(1))
+                }))
+        );
+        self.gl.apply(
+            Gl::settings()
+                .program(self.program.clone())
+                .attributes(attributes),
+            || self.elements.draw_instanced(PrimitiveType::Triangles, count)
+        );
+        return Ok(());
+    }
+}
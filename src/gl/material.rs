@@ -0,0 +1,183 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use web_sys::{
+    WebGlUniformLocation,
+    WebGlRenderingContext as Context,
+};
+
+use super::gl::Gl;
+use super::settings::Settings;
+use super::settings::SettingsCache;
+use super::program::Program;
+use super::texture::Texture;
+use super::error::GlError;
+use super::color::Color;
+
+/**
+ * Value of a uniform, matrices are column major like in GLSL.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UniformValue {
+    Float(f32),
+    Vec2([f32; 2]),
+    Vec3([f32; 3]),
+    Vec4([f32; 4]),
+    Int(i32),
+    Mat3([f32; 9]),
+    Mat4([f32; 16]),
+}
+
+impl UniformValue {
+    pub(self) fn upload(&self, context: &Context, location: &WebGlUniformLocation) {
+        match self {
+            UniformValue::Float(value) => context.uniform1f(Some(location), *value),
+            UniformValue::Vec2(value) => context.uniform2fv_with_f32_array(Some(location), value),
+            UniformValue::Vec3(value) => context.uniform3fv_with_f32_array(Some(location), value),
+            UniformValue::Vec4(value) => context.uniform4fv_with_f32_array(Some(location), value),
+            UniformValue::Int(value) => context.uniform1i(Some(location), *value),
+            UniformValue::Mat3(value) => context.uniform_matrix3fv_with_f32_array(Some(location), false, value),
+            UniformValue::Mat4(value) => context.uniform_matrix4fv_with_f32_array(Some(location), false, value),
+        }
+    }
+}
+
+impl From<f32> for UniformValue {
+    fn from(value: f32) -> Self {
+        UniformValue::Float(value)
+    }
+}
+
+impl From<[f32; 2]> for UniformValue {
+    fn from(value: [f32; 2]) -> Self {
+        UniformValue::Vec2(value)
+    }
+}
+
+impl From<[f32; 3]> for UniformValue {
+    fn from(value: [f32; 3]) -> Self {
+        UniformValue::Vec3(value)
+    }
+}
+
+impl From<[f32; 4]> for UniformValue {
+    fn from(value: [f32; 4]) -> Self {
+        UniformValue::Vec4(value)
+    }
+}
+
+impl From<i32> for UniformValue {
+    fn from(value: i32) -> Self {
+        UniformValue::Int(value)
+    }
+}
+
+impl From<[f32; 9]> for UniformValue {
+    fn from(value: [f32; 9]) -> Self {
+        UniformValue::Mat3(value)
+    }
+}
+
+impl From<[f32; 16]> for UniformValue {
+    fn from(value: [f32; 16]) -> Self {
+        UniformValue::Mat4(value)
+    }
+}
+
+impl From<Color> for UniformValue {
+    fn from(value: Color) -> Self {
+        UniformValue::Vec4(value.to_f32_array())
+    }
+}
+
+/**
+ * Program with the uniform values and textures it's drawn with. Draw inside
+ * `gl.apply(material.settings()?, || ...)`, it binds the program, uploads the uniforms
+ * and binds the textures to units `0..N` in the order they were first set.
+ * Names the program doesn't use are skipped, since GLSL compilers remove unused uniforms.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Material {
+    pub(self) program: Program,
+    pub(self) uniforms: BTreeMap<String, UniformValue>,
+    pub(self) textures: Vec<(String, Texture)>,
+}
+
+impl Material {
+    pub fn new(program: Program) -> Material {
+        Material {
+            program: program,
+            uniforms: BTreeMap::new(),
+            textures: Vec::new(),
+        }
+    }
+
+    pub fn program(&self) -> Program {
+        self.program.clone()
+    }
+
+    pub fn get(&self, name: &str) -> Option<UniformValue> {
+        self.uniforms.get(name).cloned()
+    }
+
+    pub fn set<V: Into<UniformValue>>(&mut self, name: &str, value: V) {
+        self.uniforms.insert(name.to_string(), value.into());
+    }
+
+    /**
+     * Texture for the sampler uniform, replacing a previous one keeps its unit.
+     */
+    pub fn set_texture(&mut self, name: &str, texture: Texture) {
+        match self.textures.iter_mut().find(|(sampler, _)| sampler == name) {
+            Some(binding) => binding.1 = texture,
+            None => self.textures.push((name.to_string(), texture)),
+        }
+    }
+
+    /**
+     * Setting applying the whole material, fails if there are more textures than texture units.
+     */
+    pub fn settings(&self) -> Result<MaterialSetting, GlError> {
+        let gl = self.program.gl();
+        if self.textures.len() > gl.max_texture_units() as usize {
+            return Err(GlError::TextureUnits {
+                required: self.textures.len() as u32,
+                available: gl.max_texture_units(),
+            });
+        }
+        let uniforms: Vec<(WebGlUniformLocation, UniformValue)> = self.uniforms.iter()
+            .filter_map(|(name, value)| self.program.uniform_location(name).map(|location| (location, *value)))
+            .collect();
+        return Ok(MaterialSetting {
+            program: self.program.clone(),
+            uniforms: uniforms,
+            textures: self.textures.clone(),
+        });
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MaterialSetting {
+    pub(self) program: Program,
+    pub(self) uniforms: Vec<(WebGlUniformLocation, UniformValue)>,
+    pub(self) textures: Vec<(String, Texture)>,
+}
+
+impl Settings for MaterialSetting {
+    fn apply<R, F: FnOnce() -> R>(&self, gl: &Gl, cache: &RefCell<SettingsCache>, callback: F) -> R {
+        Gl::settings().program(self.program.clone()).apply(gl, cache, || {
+            // Uniforms are state of the program, there is nothing to restore
+            for (location, value) in self.uniforms.iter() {
+                value.upload(gl.context(), location);
+            }
+            // Sampler units are state of the program too, other materials of the program may have changed them
+            let bindings: Vec<(&str, &Texture)> = self.textures.iter()
+                .map(|(name, texture)| (name.as_str(), texture))
+                .collect();
+            match self.program.bind_textures(&bindings) {
+                Ok(textures) => textures.apply(gl, cache, callback),
+                // Unreachable, `Material::settings` checked the texture unit count
+                Err(_) => callback(),
+            }
+        })
+    }
+}
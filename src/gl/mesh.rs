@@ -0,0 +1,186 @@
+use web_sys::console;
+
+use super::gl::Gl;
+use super::settings::Settings;
+use super::attribute::AttributeLayout;
+use super::data_buffer::ArrayBuffer;
+use super::data_buffer::ElementBuffer;
+use super::data_buffer::BufferUsage;
+use super::primitive::PrimitiveType;
+use super::program::Program;
+use super::error::GlError;
+
+/**
+ * Indexed triangles with a single position attribute, the shortest path from data to a draw call.
+ * Positions are 3 floats per vertex read by the `a_position` attribute of the program.
+ */
+#[derive(Clone, Debug)]
+pub struct Mesh {
+    pub(self) vertices: ArrayBuffer,
+    pub(self) elements: ElementBuffer,
+    pub(self) program: Program,
+}
+
+impl Mesh {
+    /**
+     * Fails with `AttributeMissing` if the program has no active `a_position` attribute.
+     */
+    pub fn new(gl: Gl, positions: &[f32], indices: &[u16], program: Program) -> Result<Mesh, GlError> {
+        if program.attribute_location("a_position").is_none() {
+            return Err(GlError::AttributeMissing("a_position".to_string()));
+        }
+        return Ok(Mesh {
+            vertices: ArrayBuffer::new(gl.clone(), positions, BufferUsage::Static),
+            elements: ElementBuffer::new(gl, indices, BufferUsage::Static)?,
+            program: program,
+        });
+    }
+
+    /**
+     * Per vertex tangents for normal mapping, from indexed triangles. The xyz is the direction of increasing u
+     * orthogonal to the normal, w is the handedness, the bitangent is `cross(normal, tangent.xyz) * tangent.w`.
+     * Triangles with degenerate texture coordinates are skipped, vertices without a usable tangent get any
+     * vector perpendicular to the normal.
+     */
+    pub fn compute_tangents(positions: &[[f32; 3]], normals: &[[f32; 3]], uvs: &[[f32; 2]], indices: &[u16]) -> Vec<[f32; 4]> {
+        let sub = |a: [f32; 3], b: [f32; 3]| [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+        let dot = |a: [f32; 3], b: [f32; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+        let cross = |a: [f32; 3], b: [f32; 3]| [
+            a[1] * b[2] - a[2] * b[1],
+            a[2] * b[0] - a[0] * b[2],
+            a[0] * b[1] - a[1] * b[0],
+        ];
+        let normalize = |a: [f32; 3]| {
+            let length = dot(a, a).sqrt();
+            if length > 1e-12 { Some([a[0] / length, a[1] / length, a[2] / length]) } else { None }
+        };
+
+        let count = positions.len().min(normals.len()).min(uvs.len());
+        let mut tangents = vec![[0.0f32; 3]; count];
+        let mut bitangents = vec![[0.0f32; 3]; count];
+        for triangle in indices.chunks_exact(3) {
+            let (i0, i1, i2) = (triangle[0] as usize, triangle[1] as usize, triangle[2] as usize);
+            if i0 >= count || i1 >= count || i2 >= count {
+                continue;
+            }
+            let edge1 = sub(positions[i1], positions[i0]);
+            let edge2 = sub(positions[i2], positions[i0]);
+            let (du1, dv1) = (uvs[i1][0] - uvs[i0][0], uvs[i1][1] - uvs[i0][1]);
+            let (du2, dv2) = (uvs[i2][0] - uvs[i0][0], uvs[i2][1] - uvs[i0][1]);
+            let determinant = du1 * dv2 - du2 * dv1;
+            if determinant.abs() < 1e-12 {
+                continue;
+            }
+            let r = 1.0 / determinant;
+            let tangent = [
+                (edge1[0] * dv2 - edge2[0] * dv1) * r,
+                (edge1[1] * dv2 - edge2[1] * dv1) * r,
+                (edge1[2] * dv2 - edge2[2] * dv1) * r,
+            ];
+            let bitangent = [
+                (edge2[0] * du1 - edge1[0] * du2) * r,
+                (edge2[1] * du1 - edge1[1] * du2) * r,
+                (edge2[2] * du1 - edge1[2] * du2) * r,
+            ];
+            for &i in [i0, i1, i2].iter() {
+                for axis in 0..3 {
+                    tangents[i][axis] += tangent[axis];
+                    bitangents[i][axis] += bitangent[axis];
+                }
+            }
+        }
+
+        return (0..count).map(|i| {
+            let normal = normalize(normals[i]).unwrap_or([0.0, 0.0, 1.0]);
+            // Gram-Schmidt, keep only the part of the tangent orthogonal to the normal
+            let along_normal = dot(normal, tangents[i]);
+            let projected = [normal[0] * along_normal, normal[1] * along_normal, normal[2] * along_normal];
+            let tangent = normalize(sub(tangents[i], projected))
+                .unwrap_or_else(|| {
+                    let axis = if normal[0].abs() < 0.9 { [1.0, 0.0, 0.0] } else { [0.0, 1.0, 0.0] };
+                    normalize(cross(axis, normal)).unwrap_or([1.0, 0.0, 0.0])
+                });
+            let handedness = if dot(cross(normal, tangent), bitangents[i]) < 0.0 { -1.0 } else { 1.0 };
+            [tangent[0], tangent[1], tangent[2], handedness]
+        }).collect();
+    }
+
+    pub fn vertices(&self) -> ArrayBuffer {
+        self.vertices.clone()
+    }
+
+    pub fn elements(&self) -> ElementBuffer {
+        self.elements.clone()
+    }
+
+    pub fn program(&self) -> Program {
+        self.program.clone()
+    }
+
+    /**
+     * Draw the triangles with the program, uniforms are set by the caller beforehand. Nothing is drawn
+     * and a warning is logged if the program lost `a_position`, e.g. after a `reload`.
+     */
+    pub fn draw(&self) {
+        let location = match self.program.attribute_location("a_position") {
+            Some(location) => location,
+            None => {
+                console::warn_1(&"rwgl: mesh program has no active a_position attribute, nothing is drawn".into());
+                return;
+            },
+        };
+        self.program.gl().apply(
+            Gl::settings()
+                .program(self.program.clone())
+                .attribute(location, self.vertices.clone(), AttributeLayout::floats(3)),
+            || self.elements.draw(PrimitiveType::Triangles)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUAD_POSITIONS: [[f32; 3]; 4] = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]];
+    const QUAD_NORMALS: [[f32; 3]; 4] = [[0.0, 0.0, 1.0]; 4];
+    const QUAD_INDICES: [u16; 6] = [0, 1, 2, 0, 2, 3];
+
+    #[test]
+    fn unit_quad_tangents_follow_u() {
+        let uvs = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]];
+        let tangents = Mesh::compute_tangents(&QUAD_POSITIONS, &QUAD_NORMALS, &uvs, &QUAD_INDICES);
+        assert_eq!(tangents, vec![[1.0, 0.0, 0.0, 1.0]; 4]);
+    }
+
+    #[test]
+    fn mirrored_uvs_flip_handedness() {
+        let uvs = [[1.0, 0.0], [0.0, 0.0], [0.0, 1.0], [1.0, 1.0]];
+        let tangents = Mesh::compute_tangents(&QUAD_POSITIONS, &QUAD_NORMALS, &uvs, &QUAD_INDICES);
+        assert_eq!(tangents, vec![[-1.0, 0.0, 0.0, -1.0]; 4]);
+    }
+
+    #[test]
+    fn degenerate_uvs_give_perpendicular_tangents() {
+        let uvs = [[0.5, 0.5]; 4];
+        let tangents = Mesh::compute_tangents(&QUAD_POSITIONS, &QUAD_NORMALS, &uvs, &QUAD_INDICES);
+        assert_eq!(tangents.len(), 4);
+        for tangent in tangents {
+            assert!(tangent.iter().all(|value| value.is_finite()));
+            let length = (tangent[0] * tangent[0] + tangent[1] * tangent[1] + tangent[2] * tangent[2]).sqrt();
+            assert!((length - 1.0).abs() < 1e-6);
+            assert!(tangent[2].abs() < 1e-6);
+            assert_eq!(tangent[3].abs(), 1.0);
+        }
+    }
+
+    #[test]
+    fn out_of_range_indices_are_skipped() {
+        let uvs = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]];
+        let indices = [0, 1, 2, 0, 2, 7];
+        let tangents = Mesh::compute_tangents(&QUAD_POSITIONS, &QUAD_NORMALS, &uvs, &indices);
+        assert_eq!(&tangents[..3], &[[1.0, 0.0, 0.0, 1.0]; 3]);
+        // Not part of any valid triangle, so it gets the fallback perpendicular to the normal
+        assert_eq!(tangents[3], [0.0, -1.0, 0.0, 1.0]);
+    }
+}
@@ -3,12 +3,94 @@ mod settings;
 mod texture;
 mod data_buffer;
 mod program;
+mod error;
+mod query;
+mod sampler;
+mod thread_bound;
+mod primitive;
+mod framebuffer;
+mod attribute;
+mod sprite_batch;
+mod color;
+mod render_target;
+mod render_graph;
+mod renderbuffer;
+mod context_options;
+mod mesh;
+mod texture_array;
+mod instanced_renderer;
+mod draw_queue;
+mod material;
+mod text_renderer;
+mod pod;
+mod buffer_arena;
+#[cfg(feature = "testing")]
+pub mod testing;
 
 pub use self::gl::Gl;
+pub use self::gl::AttribDebugInfo;
+pub use self::gl::TextureUnitLimits;
+pub use self::gl::Limits;
+pub use self::gl::CapabilityReport;
+pub use self::gl::Capability;
+pub use self::gl::ResourceCounts;
+pub use self::context_options::ContextOptions;
+pub use self::error::GlError;
+pub use self::color::Color;
 pub use self::texture::Texture;
 pub use self::texture::TextureType;
+pub use self::texture::TextureFormat;
 pub use self::texture::TextureFilter;
+pub use self::texture::MinFilter;
+pub use self::texture::TextureWrap;
 pub use self::texture::TextureContent;
+pub use self::texture_array::TextureArray;
 pub use self::data_buffer::ArrayBuffer;
 pub use self::data_buffer::ArrayBufferData;
 pub use self::data_buffer::BufferUsage;
+pub use self::data_buffer::ElementBuffer;
+pub use self::data_buffer::IndexType;
+pub use self::data_buffer::IndexData;
+pub use self::buffer_arena::BufferArena;
+pub use self::pod::Pod;
+pub use self::primitive::PrimitiveType;
+pub use self::program::Program;
+pub use self::program::ShaderType;
+pub use self::program::PrecisionType;
+pub use self::program::PrecisionFormat;
+pub use self::program::GlslVersion;
+pub use self::program::AttributeInfo;
+pub use self::program::UniformInfo;
+pub use self::query::TimerQuery;
+pub use self::query::OcclusionQuery;
+pub use self::sampler::Sampler;
+pub use self::settings::SettingsCache;
+pub use self::settings::DynSettings;
+pub use self::settings::BlendFactor;
+pub use self::settings::BlendFuncSetting;
+pub use self::settings::BlendFuncSeparateSetting;
+pub use self::settings::CompareFunction;
+pub use self::settings::StencilOp;
+pub use self::settings::Quality;
+pub use self::thread_bound::ThreadBound;
+pub use self::framebuffer::Framebuffer;
+pub use self::renderbuffer::Renderbuffer;
+pub use self::renderbuffer::RenderbufferFormat;
+pub use self::render_target::RenderTarget;
+pub use self::render_target::PingPong;
+pub use self::render_graph::Pass;
+pub use self::render_graph::RenderGraph;
+pub use self::attribute::AttributeType;
+pub use self::attribute::AttributeLayout;
+pub use self::attribute::VertexFormat;
+pub use self::settings::AttributeSetting;
+pub use self::settings::ViewportSetting;
+pub use self::sprite_batch::Sprite;
+pub use self::sprite_batch::SpriteBatch;
+pub use self::mesh::Mesh;
+pub use self::instanced_renderer::InstancedRenderer;
+pub use self::draw_queue::DrawQueue;
+pub use self::material::Material;
+pub use self::material::UniformValue;
+pub use self::text_renderer::Glyph;
+pub use self::text_renderer::TextRenderer;
@@ -0,0 +1,38 @@
+/**
+ * Plain old data that can be uploaded to GL as its raw bytes.
+ *
+ * # Safety
+ *
+ * Implement it only for `Copy` types without padding, pointers or references, where every bit pattern
+ * is valid, e.g. a `#[repr(C)]` struct of `f32` fields. Reading padding bytes is undefined behavior.
+ */
+pub unsafe trait Pod: Copy + 'static {}
+
+unsafe impl Pod for u8 {}
+unsafe impl Pod for i8 {}
+unsafe impl Pod for u16 {}
+unsafe impl Pod for i16 {}
+unsafe impl Pod for u32 {}
+unsafe impl Pod for i32 {}
+unsafe impl Pod for u64 {}
+unsafe impl Pod for i64 {}
+unsafe impl Pod for f32 {}
+unsafe impl Pod for f64 {}
+
+macro_rules! impl_pod_arrays {
+    ($($len:expr),*) => {
+        $(unsafe impl <T: Pod> Pod for [T; $len] {})*
+    };
+}
+
+impl_pod_arrays!(1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16);
+
+/**
+ * Bytes of the slice in memory order.
+ */
+pub(super) fn bytes_of<T: Pod>(data: &[T]) -> &[u8] {
+    // Pod types have no padding, so every byte is initialized
+    unsafe {
+        std::slice::from_raw_parts(data as *const [T] as *const u8, std::mem::size_of_val(data))
+    }
+}
@@ -0,0 +1,17 @@
+use web_sys::WebGlRenderingContext as Context;
+use num_enum::{
+    TryFromPrimitive,
+    IntoPrimitive,
+};
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, TryFromPrimitive, IntoPrimitive, PartialEq, Eq)]
+pub enum PrimitiveType {
+    Points = Context::POINTS,
+    Lines = Context::LINES,
+    LineLoop = Context::LINE_LOOP,
+    LineStrip = Context::LINE_STRIP,
+    Triangles = Context::TRIANGLES,
+    TriangleStrip = Context::TRIANGLE_STRIP,
+    TriangleFan = Context::TRIANGLE_FAN,
+}
@@ -1,26 +1,630 @@
+use std::rc::Rc;
+use std::rc::Weak;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::collections::hash_map::DefaultHasher;
+use std::cell::RefCell;
 use std::collections::BTreeMap;
-use num_enum::{
-    TryFromPrimitive,
-    IntoPrimitive,
-};
+use std::collections::HashMap;
 use web_sys::{
     WebGlShader,
     WebGlProgram,
+    WebGlUniformLocation,
     WebGlRenderingContext as Context,
-    console,
+};
+
+use num_enum::{
+    TryFromPrimitive,
+    IntoPrimitive,
 };
 
 use super::gl::Gl;
+use super::error::GlError;
+use super::settings::Settings;
+use super::settings::ProgramSetting;
+use super::texture::Texture;
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, TryFromPrimitive, IntoPrimitive, PartialEq, Eq)]
+pub enum ShaderType {
+    Vertex = Context::VERTEX_SHADER,
+    Fragment = Context::FRAGMENT_SHADER,
+}
 
-#[derive(Clone, Copy, Debug)]
-struct AttributeInfo {
-    location: i32,
-    size_in_floats: u32,
+/**
+ * Shading language version of the context, GLSL ES 1.00 on WebGL 1 and GLSL ES 3.00 on WebGL 2.
+ * WebGL 2 compiles 1.00 shaders too, 3.00 ones need `#version 300 es` as the very first line.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GlslVersion {
+    Es100,
+    Es300,
 }
 
-#[derive(Debug, Clone)]
+impl GlslVersion {
+    pub fn directive(&self) -> &'static str {
+        match self {
+            GlslVersion::Es100 => "#version 100",
+            GlslVersion::Es300 => "#version 300 es",
+        }
+    }
+
+    /**
+     * Version declared by the `#version` directive of the source, 1.00 without one.
+     */
+    pub fn of_source(source: &str) -> GlslVersion {
+        if source.trim_start().starts_with("#version 300") {
+            GlslVersion::Es300
+        } else {
+            GlslVersion::Es100
+        }
+    }
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, TryFromPrimitive, IntoPrimitive, PartialEq, Eq)]
+pub enum PrecisionType {
+    LowFloat = Context::LOW_FLOAT,
+    MediumFloat = Context::MEDIUM_FLOAT,
+    HighFloat = Context::HIGH_FLOAT,
+    LowInt = Context::LOW_INT,
+    MediumInt = Context::MEDIUM_INT,
+    HighInt = Context::HIGH_INT,
+}
+
+/**
+ * Range is log2 of the minimum and maximum representable magnitudes, precision is log2 of the relative precision.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PrecisionFormat {
+    pub range_min: i32,
+    pub range_max: i32,
+    pub precision: i32,
+}
+
+impl PrecisionFormat {
+    /**
+     * Unsupported precisions, like `highp` in fragment shaders on some mobile GPUs, are reported as all zeros.
+     */
+    pub fn is_supported(&self) -> bool {
+        *self != PrecisionFormat::default()
+    }
+}
+
+/**
+ * Active attribute of a linked program.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AttributeInfo {
+    pub(self) location: i32,
+    pub(self) size_in_floats: u32,
+}
+
+impl AttributeInfo {
+    pub fn location(&self) -> u32 {
+        self.location as u32
+    }
+
+    /**
+     * Floats per vertex, times the array length for attribute arrays.
+     */
+    pub fn size_in_floats(&self) -> u32 {
+        self.size_in_floats
+    }
+}
+
+/**
+ * Active uniform of a linked program, arrays are listed once by the name without `[0]`.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct UniformInfo {
+    pub(self) location: WebGlUniformLocation,
+    pub(self) data_type: u32,
+    pub(self) size: u32,
+}
+
+impl UniformInfo {
+    pub fn location(&self) -> WebGlUniformLocation {
+        self.location.clone()
+    }
+
+    /**
+     * GL type constant, e.g. `FLOAT_VEC3` or `SAMPLER_2D`.
+     */
+    pub fn data_type(&self) -> u32 {
+        self.data_type
+    }
+
+    /**
+     * Array length, 1 for plain uniforms.
+     */
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+}
+
+#[derive(Debug)]
 pub struct ProgramData {
     pub(self) gl: Gl,
+    pub(self) id: u64,
     pub(self) handle: WebGlProgram,
     pub(self) attributes: BTreeMap<String, AttributeInfo>,
-}
\ No newline at end of file
+    pub(self) uniforms: BTreeMap<String, UniformInfo>,
+    /**
+     * Locations bound before linking, reused by `reload`.
+     */
+    pub(self) attribute_bindings: Vec<(String, u32)>,
+    pub(self) disposed: bool,
+    pub(self) label: Option<String>,
+}
+
+impl Drop for ProgramData {
+    fn drop(&mut self) {
+        if !self.disposed {
+            self.gl.context().delete_program(Some(&self.handle));
+        }
+        self.gl.track_resources(|counts| counts.programs = counts.programs.saturating_sub(1));
+    }
+}
+
+/**
+ * Linked shader program. Clones share the same GL program, `reload` replaces it for all of them.
+ */
+#[derive(Clone, Debug)]
+pub struct Program {
+    pub(self) data: Rc<RefCell<ProgramData>>,
+}
+
+impl PartialEq<Program> for Program {
+    fn eq(&self, other: &Program) -> bool {
+        Rc::ptr_eq(&self.data, &other.data)
+    }
+}
+
+impl Eq for Program {}
+
+impl Program {
+    pub fn new(gl: Gl, vertex_src: &str, fragment_src: &str) -> Result<Program, GlError> {
+        Program::new_with_attribute_locations(gl, vertex_src, fragment_src, &[])
+    }
+
+    /**
+     * Bind the attributes to the locations before linking, instead of letting the driver assign them,
+     * so one attribute configuration works with every program using the same locations.
+     * Attributes missing from the list are still assigned by the driver.
+     */
+    pub fn new_with_attribute_locations(
+        gl: Gl,
+        vertex_src: &str,
+        fragment_src: &str,
+        locations: &[(&str, u32)],
+    ) -> Result<Program, GlError> {
+        let bindings: Vec<(String, u32)> = locations.iter()
+            .map(|(name, location)| (name.to_string(), *location))
+            .collect();
+        let data = Program::link(&gl, vertex_src, fragment_src, bindings)?;
+        return Ok(Program {
+            data: Rc::new(RefCell::new(data)),
+        });
+    }
+
+    /**
+     * Compile the program with the `#version` directive of `Gl::glsl_version` prepended to sources without one,
+     * and `precision mediump float;` to a fragment source without a precision statement.
+     * Sources are written for the context version, e.g. with `in` and `out` on WebGL 2.
+     */
+    pub fn new_with_version_header(gl: Gl, vertex_src: &str, fragment_src: &str) -> Result<Program, GlError> {
+        let version = gl.glsl_version();
+        let vertex_src = Program::add_version_header(vertex_src, version, false);
+        let fragment_src = Program::add_version_header(fragment_src, version, true);
+        return Program::new(gl, &vertex_src, &fragment_src);
+    }
+
+    pub(self) fn add_version_header(source: &str, version: GlslVersion, fragment: bool) -> String {
+        let precision = if fragment && !source.lines().any(|line| line.trim().starts_with("precision ")) {
+            "precision mediump float;\n"
+        } else {
+            ""
+        };
+        if !source.trim_start().starts_with("#version") {
+            return format!("{}\n{}{}", version.directive(), precision, source);
+        }
+        // The precision statement has to come after the #version line
+        let source = source.trim_start();
+        let (directive, body) = source.split_at(source.find('\n').map_or(source.len(), |end| end + 1));
+        if directive.ends_with('\n') {
+            return format!("{}{}{}", directive, precision, body);
+        } else {
+            return format!("{}\n{}", directive, precision);
+        }
+    }
+
+    /**
+     * Compile the program resolving `#include "name"` directives against the map, includes can be nested.
+     * `#line` directives are inserted so compile errors refer to lines of the original files, the source
+     * string number in an error log is the position of the include in the legend appended to the log.
+     */
+    pub fn new_with_includes(
+        gl: Gl,
+        vertex_src: &str,
+        fragment_src: &str,
+        includes: &HashMap<String, String>,
+    ) -> Result<Program, GlError> {
+        let mut names = Vec::new();
+        let vertex_src = Program::resolve_includes(vertex_src, includes, &mut names)?;
+        let fragment_src = Program::resolve_includes(fragment_src, includes, &mut names)?;
+        return Program::new(gl, &vertex_src, &fragment_src).map_err(|error| match error {
+            GlError::ShaderCompile(log) => {
+                let legend: Vec<String> = names.iter()
+                    .enumerate()
+                    .map(|(i, name)| format!("{}: {}", i + 1, name))
+                    .collect();
+                GlError::ShaderCompile(format!("{}\nsource strings: 0: main, {}", log, legend.join(", ")))
+            },
+            error => error,
+        });
+    }
+
+    /**
+     * Compile the program with `#define NAME VALUE` lines injected into both shaders, after `#version` if present.
+     * Line numbers in compile errors still match the original sources.
+     */
+    pub fn new_with_defines(gl: Gl, vertex_src: &str, fragment_src: &str, defines: &[(&str, &str)]) -> Result<Program, GlError> {
+        let vertex_src = Program::inject_defines(vertex_src, defines);
+        let fragment_src = Program::inject_defines(fragment_src, defines);
+        return Program::new(gl, &vertex_src, &fragment_src);
+    }
+
+    pub(self) fn inject_defines(source: &str, defines: &[(&str, &str)]) -> String {
+        let defines: String = defines.iter()
+            .map(|(name, value)| format!("#define {} {}\n", name, value))
+            .collect();
+        // #version has to stay the very first line
+        let header_end = if source.trim_start().starts_with("#version") {
+            let start = source.len() - source.trim_start().len();
+            source[start..].find('\n').map_or(source.len(), |end| start + end + 1)
+        } else {
+            0
+        };
+        let (header, body) = source.split_at(header_end);
+        let next_line = header.lines().count() + 1;
+        let line_shift = if source.trim_start().starts_with("#version 300") { 0 } else { 1 };
+        let header = if header.is_empty() || header.ends_with('\n') {
+            header.to_string()
+        } else {
+            format!("{}\n", header)
+        };
+        return format!("{}{}#line {}\n{}", header, defines, next_line - line_shift, body);
+    }
+
+    pub(self) fn resolve_includes(source: &str, includes: &HashMap<String, String>, names: &mut Vec<String>) -> Result<String, GlError> {
+        // GLSL ES 1.00 #line sets the number of the line before the next one, GLSL ES 3.00 of the next one
+        let line_shift = if source.trim_start().starts_with("#version 300") { 0 } else { 1 };
+        let mut stack = Vec::new();
+        return Program::resolve_source(source, 0, line_shift, includes, names, &mut stack);
+    }
+
+    pub(self) fn resolve_source(
+        source: &str,
+        source_id: usize,
+        line_shift: usize,
+        includes: &HashMap<String, String>,
+        names: &mut Vec<String>,
+        stack: &mut Vec<String>,
+    ) -> Result<String, GlError> {
+        let mut result = String::new();
+        for (i, line) in source.lines().enumerate() {
+            let name = line.trim()
+                .strip_prefix("#include")
+                .map(|name| name.trim().trim_matches(|c| c == '"' || c == '<' || c == '>'));
+            match name {
+                Some(name) => {
+                    if stack.iter().any(|included| included == name) {
+                        stack.push(name.to_string());
+                        return Err(GlError::IncludeCycle(stack.join(" -> ")));
+                    }
+                    let content = includes.get(name).ok_or_else(|| GlError::IncludeNotFound(name.to_string()))?;
+                    let id = match names.iter().position(|included| included == name) {
+                        Some(position) => position + 1,
+                        None => {
+                            names.push(name.to_string());
+                            names.len()
+                        }
+                    };
+
+                    stack.push(name.to_string());
+                    result.push_str(&format!("#line {} {}\n", 1 - line_shift, id));
+                    result.push_str(&Program::resolve_source(content, id, line_shift, includes, names, stack)?);
+                    result.push_str(&format!("#line {} {}\n", i + 2 - line_shift, source_id));
+                    stack.pop();
+                },
+                None => {
+                    result.push_str(line);
+                    result.push('\n');
+                },
+            }
+        }
+        return Ok(result);
+    }
+
+    /**
+     * Recompile the program in place, every clone of it picks up the new shaders.
+     * On failure the old program is kept and the error is returned.
+     */
+    pub fn reload(&self, vertex_src: &str, fragment_src: &str) -> Result<(), GlError> {
+        let gl = self.gl();
+        let bindings = self.data.borrow().attribute_bindings.clone();
+        let mut data = Program::link(&gl, vertex_src, fragment_src, bindings)?;
+        data.label = self.label();
+        // The program keeps its identity, only the GL program is replaced
+        data.id = self.id();
+        let previous = std::mem::replace(&mut *self.data.borrow_mut(), data);
+        ProgramSetting::refresh(&gl, self);
+        gl.program_cache().borrow_mut().rekey(self, vertex_src, fragment_src);
+        drop(previous);
+        return Ok(());
+    }
+
+    pub fn gl(&self) -> Gl {
+        self.data.borrow().gl.clone()
+    }
+
+    pub(super) fn handle(&self) -> WebGlProgram {
+        self.data.borrow().handle.clone()
+    }
+
+    /**
+     * Id increasing in creation order, a cheap key for sorting draws by program. Kept by `reload`.
+     */
+    pub fn id(&self) -> u64 {
+        self.data.borrow().id
+    }
+
+    pub fn label(&self) -> Option<String> {
+        self.data.borrow().label.clone()
+    }
+
+    /**
+     * Name shown in `Debug` output, `reload` keeps it.
+     */
+    pub fn set_label(&self, label: &str) {
+        self.data.borrow_mut().label = Some(label.to_string());
+    }
+
+    pub fn dispose(&self) {
+        let mut data = self.data.borrow_mut();
+        if !data.disposed {
+            data.disposed = true;
+            data.gl.context().delete_program(Some(&data.handle));
+        }
+    }
+
+    pub fn is_disposed(&self) -> bool {
+        self.data.borrow().disposed
+    }
+
+    pub fn attribute_location(&self, name: &str) -> Option<u32> {
+        self.data.borrow().attributes.get(name).map(|info| info.location as u32)
+    }
+
+    pub fn uniform_location(&self, name: &str) -> Option<WebGlUniformLocation> {
+        self.data.borrow().uniforms.get(name).map(|info| info.location.clone())
+    }
+
+    /**
+     * Active attributes sorted by name, e.g. for shader debugging tools. The list is a snapshot,
+     * call it again after `reload`.
+     */
+    pub fn attributes(&self) -> impl Iterator<Item = (String, AttributeInfo)> {
+        let attributes: Vec<(String, AttributeInfo)> = self.data.borrow().attributes.iter()
+            .map(|(name, info)| (name.clone(), *info))
+            .collect();
+        return attributes.into_iter();
+    }
+
+    /**
+     * Active uniforms sorted by name, a snapshot like `attributes`.
+     */
+    pub fn uniforms(&self) -> impl Iterator<Item = (String, UniformInfo)> {
+        let uniforms: Vec<(String, UniformInfo)> = self.data.borrow().uniforms.iter()
+            .map(|(name, info)| (name.clone(), info.clone()))
+            .collect();
+        return uniforms.into_iter();
+    }
+
+    /**
+     * Assign texture units `0..N` to the named samplers in order, set the sampler uniforms and return
+     * the setting binding each texture to its unit. Samplers the program doesn't use keep their unit unused.
+     */
+    pub fn bind_textures(&self, bindings: &[(&str, &Texture)]) -> Result<impl Settings, GlError> {
+        let ref gl = self.gl();
+        let available = gl.max_texture_units();
+        if bindings.len() > available as usize {
+            return Err(GlError::TextureUnits {
+                required: bindings.len() as u32,
+                available: available,
+            });
+        }
+        gl.apply(Gl::settings().program(self.clone()), || {
+            for (unit, (name, _)) in bindings.iter().enumerate() {
+                if let Some(location) = self.uniform_location(name) {
+                    gl.context().uniform1i(Some(&location), unit as i32);
+                }
+            }
+        });
+        let textures = bindings.iter()
+            .enumerate()
+            .map(|(unit, (_, texture))| (unit as u32, (*texture).clone()))
+            .collect();
+        return Ok(Gl::settings().textures(textures));
+    }
+
+    pub(self) fn declares_sampler(source: &str) -> bool {
+        source.lines()
+            .map(|line| line.split("//").next().unwrap_or("").trim())
+            .any(|line| line.starts_with("uniform") && line.contains("sampler"))
+    }
+
+    pub(self) fn compile_shader(context: &Context, shader_type: u32, source: &str) -> Result<WebGlShader, GlError> {
+        let shader = context.create_shader(shader_type).unwrap();
+        context.shader_source(&shader, source);
+        context.compile_shader(&shader);
+        if context.get_shader_parameter(&shader, Context::COMPILE_STATUS).as_bool().unwrap_or(false) {
+            return Ok(shader);
+        } else {
+            let log = context.get_shader_info_log(&shader).unwrap_or_default();
+            context.delete_shader(Some(&shader));
+            return Err(GlError::ShaderCompile(log));
+        }
+    }
+
+    /**
+     * Source as GL gets it, blank lines before `#version` are dropped, the directive has to come first.
+     */
+    pub(self) fn versioned_source<'a>(gl: &Gl, source: &'a str) -> Result<&'a str, GlError> {
+        if !source.trim_start().starts_with("#version") {
+            return Ok(source);
+        }
+        if GlslVersion::of_source(source) > gl.glsl_version() {
+            return Err(GlError::WebGl2Required);
+        }
+        return Ok(source.trim_start());
+    }
+
+    pub(self) fn link(gl: &Gl, vertex_src: &str, fragment_src: &str, attribute_bindings: Vec<(String, u32)>) -> Result<ProgramData, GlError> {
+        let context = gl.context();
+        let vertex_src = Program::versioned_source(gl, vertex_src)?;
+        let fragment_src = Program::versioned_source(gl, fragment_src)?;
+        // Binding a texture for a vertex sampler silently fails without vertex texture units
+        if gl.texture_unit_limits().vertex == 0 && Program::declares_sampler(vertex_src) {
+            return Err(GlError::VertexTexturesUnsupported);
+        }
+        let vertex = Program::compile_shader(context, Context::VERTEX_SHADER, vertex_src)?;
+        let fragment = match Program::compile_shader(context, Context::FRAGMENT_SHADER, fragment_src) {
+            Ok(fragment) => fragment,
+            Err(error) => {
+                context.delete_shader(Some(&vertex));
+                return Err(error);
+            }
+        };
+
+        let handle = context.create_program().unwrap();
+        context.attach_shader(&handle, &vertex);
+        context.attach_shader(&handle, &fragment);
+        for (name, location) in attribute_bindings.iter() {
+            context.bind_attrib_location(&handle, *location, name);
+        }
+        context.link_program(&handle);
+        // Attached shaders are only flagged for deletion, they live as long as the program
+        context.delete_shader(Some(&vertex));
+        context.delete_shader(Some(&fragment));
+
+        if !context.get_program_parameter(&handle, Context::LINK_STATUS).as_bool().unwrap_or(false) {
+            let log = context.get_program_info_log(&handle).unwrap_or_default();
+            context.delete_program(Some(&handle));
+            return Err(GlError::ProgramLink(log));
+        }
+
+        let mut attributes = BTreeMap::new();
+        let attributes_count = context.get_program_parameter(&handle, Context::ACTIVE_ATTRIBUTES).as_f64().unwrap_or(0.0) as u32;
+        for i in 0..attributes_count {
+            if let Some(info) = context.get_active_attrib(&handle, i) {
+                attributes.insert(info.name(), AttributeInfo {
+                    location: context.get_attrib_location(&handle, &info.name()),
+                    size_in_floats: Program::size_in_floats(info.type_()) * info.size() as u32,
+                });
+            }
+        }
+
+        let mut uniforms = BTreeMap::new();
+        let uniforms_count = context.get_program_parameter(&handle, Context::ACTIVE_UNIFORMS).as_f64().unwrap_or(0.0) as u32;
+        for i in 0..uniforms_count {
+            if let Some(info) = context.get_active_uniform(&handle, i) {
+                if let Some(location) = context.get_uniform_location(&handle, &info.name()) {
+                    // Arrays are reported as `name[0]`, make them available by the plain name
+                    let name = info.name().trim_end_matches("[0]").to_string();
+                    uniforms.insert(name, UniformInfo {
+                        location: location,
+                        data_type: info.type_(),
+                        size: info.size() as u32,
+                    });
+                }
+            }
+        }
+
+        gl.track_resources(|counts| counts.programs += 1);
+        return Ok(ProgramData {
+            gl: gl.clone(),
+            id: gl.next_id(),
+            handle: handle,
+            attributes: attributes,
+            uniforms: uniforms,
+            attribute_bindings: attribute_bindings,
+            disposed: false,
+            label: None,
+        });
+    }
+
+    pub(self) fn size_in_floats(data_type: u32) -> u32 {
+        match data_type {
+            Context::FLOAT_VEC2 => 2,
+            Context::FLOAT_VEC3 => 3,
+            Context::FLOAT_VEC4 | Context::FLOAT_MAT2 => 4,
+            Context::FLOAT_MAT3 => 9,
+            Context::FLOAT_MAT4 => 16,
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CachedProgram {
+    vertex_src: String,
+    fragment_src: String,
+    program: Weak<RefCell<ProgramData>>,
+}
+
+/**
+ * Compiled programs by source hash. Programs are held weakly, so the cache neither keeps them alive
+ * nor creates a reference cycle with `Gl`. A program reloaded with other sources is still found by its original ones.
+ */
+#[derive(Debug, Default)]
+pub struct ProgramCache {
+    pub(self) programs: HashMap<u64, CachedProgram>,
+}
+
+impl ProgramCache {
+    pub(self) fn key(vertex_src: &str, fragment_src: &str) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        (vertex_src, fragment_src).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    pub(super) fn get(&self, vertex_src: &str, fragment_src: &str) -> Option<Program> {
+        self.programs.get(&ProgramCache::key(vertex_src, fragment_src))
+            .filter(|cached| cached.vertex_src == vertex_src && cached.fragment_src == fragment_src)
+            .and_then(|cached| cached.program.upgrade())
+            .map(|data| Program { data: data })
+            .filter(|program| !program.is_disposed())
+    }
+
+    pub(super) fn insert(&mut self, vertex_src: &str, fragment_src: &str, program: &Program) {
+        self.programs.retain(|_, cached| cached.program.upgrade().is_some());
+        self.programs.insert(ProgramCache::key(vertex_src, fragment_src), CachedProgram {
+            vertex_src: vertex_src.to_string(),
+            fragment_src: fragment_src.to_string(),
+            program: Rc::downgrade(&program.data),
+        });
+    }
+
+    /**
+     * Move a reloaded program to the key of its new sources, the old sources must not return it anymore.
+     */
+    pub(super) fn rekey(&mut self, program: &Program, vertex_src: &str, fragment_src: &str) {
+        let count = self.programs.len();
+        self.programs.retain(|_, cached| !cached.program.ptr_eq(&Rc::downgrade(&program.data)));
+        if self.programs.len() != count {
+            self.insert(vertex_src, fragment_src, program);
+        }
+    }
+}
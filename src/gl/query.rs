@@ -0,0 +1,178 @@
+use std::rc::Rc;
+use wasm_bindgen::JsValue;
+use web_sys::{
+    ExtDisjointTimerQuery,
+    WebGlQuery,
+    WebGl2RenderingContext as Context2,
+};
+
+use super::gl::Gl;
+use super::error::GlError;
+
+#[derive(Debug)]
+pub struct TimerQueryInfo {
+    pub(self) gl: Gl,
+    pub(self) handle: Option<WebGlQuery>,
+}
+
+impl Drop for TimerQueryInfo {
+    fn drop(&mut self) {
+        if let Some(context) = self.gl.timer_query_context2() {
+            context.delete_query(self.handle.as_ref());
+        } else if let Some(ex) = self.gl.ex_timer_query() {
+            ex.delete_query_ext(self.handle.as_ref());
+        }
+    }
+}
+
+/**
+ * GPU timer of `EXT_disjoint_timer_query` on WebGL 1 or `EXT_disjoint_timer_query_webgl2` on WebGL 2,
+ * created by `Gl::time_elapsed`.
+ */
+#[derive(Clone, Debug)]
+pub struct TimerQuery {
+    pub(self) data: Rc<TimerQueryInfo>,
+}
+
+impl TimerQuery {
+    pub(super) fn new(gl: Gl) -> TimerQuery {
+        let handle = match gl.timer_query_context2() {
+            Some(context) => context.create_query(),
+            None => gl.ex_timer_query().and_then(|ex| ex.create_query_ext()),
+        };
+        TimerQuery {
+            data: Rc::new(TimerQueryInfo {
+                gl: gl,
+                handle: handle,
+            })
+        }
+    }
+
+    pub(super) fn measure<R>(&self, callback: impl FnOnce() -> R) -> R {
+        let ref gl = self.data.gl;
+        let handle = match self.data.handle.as_ref() {
+            Some(handle) => handle,
+            None => return callback(),
+        };
+        if let Some(context) = gl.timer_query_context2() {
+            context.begin_query(ExtDisjointTimerQuery::TIME_ELAPSED_EXT, handle);
+            let result = callback();
+            context.end_query(ExtDisjointTimerQuery::TIME_ELAPSED_EXT);
+            return result;
+        }
+        match gl.ex_timer_query() {
+            Some(ex) => {
+                ex.begin_query_ext(ExtDisjointTimerQuery::TIME_ELAPSED_EXT, handle);
+                let result = callback();
+                ex.end_query_ext(ExtDisjointTimerQuery::TIME_ELAPSED_EXT);
+                result
+            },
+            None => callback(),
+        }
+    }
+
+    /**
+     * Elapsed GPU time in nanoseconds, `Ok(None)` while the result is not available yet.
+     * Results usually arrive a frame or two later, so poll it from subsequent frames.
+     */
+    pub fn result_ns(&self) -> Result<Option<u64>, GlError> {
+        let ref gl = self.data.gl;
+        let handle = self.data.handle.as_ref().ok_or(GlError::ExtensionMissing("EXT_disjoint_timer_query"))?;
+        // Both extensions report results with the same enums, the WebGL 2 one through the core query API
+        let result = |name: u32| match (gl.timer_query_context2(), gl.ex_timer_query()) {
+            (Some(context), _) => context.get_query_parameter(handle, name),
+            (None, Some(ex)) => ex.get_query_object_ext(handle, name),
+            (None, None) => JsValue::UNDEFINED,
+        };
+
+        let available = result(ExtDisjointTimerQuery::QUERY_RESULT_AVAILABLE_EXT)
+            .as_bool()
+            .unwrap_or(false);
+        if !available {
+            return Ok(None);
+        }
+
+        let disjoint = gl.context().get_parameter(ExtDisjointTimerQuery::GPU_DISJOINT_EXT)
+            .ok()
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false);
+        if disjoint {
+            return Err(GlError::TimerDisjoint);
+        }
+
+        let elapsed = result(ExtDisjointTimerQuery::QUERY_RESULT_EXT)
+            .as_f64()
+            .unwrap_or(0.0);
+        return Ok(Some(elapsed as u64));
+    }
+}
+
+#[derive(Debug)]
+pub struct OcclusionQueryInfo {
+    pub(self) gl: Gl,
+    pub(self) handle: WebGlQuery,
+}
+
+impl Drop for OcclusionQueryInfo {
+    fn drop(&mut self) {
+        if let Some(context) = self.gl.context2() {
+            context.delete_query(Some(&self.handle));
+        }
+    }
+}
+
+/**
+ * WebGL 2 query whether any samples of the draws inside `measure` passed the depth and stencil tests.
+ * Draw a bounding box with color and depth writes off inside it and skip the object while the box
+ * is hidden. Results arrive a frame or two later, poll them from subsequent frames.
+ */
+#[derive(Clone, Debug)]
+pub struct OcclusionQuery {
+    pub(self) data: Rc<OcclusionQueryInfo>,
+}
+
+impl OcclusionQuery {
+    pub fn new(gl: Gl) -> Result<OcclusionQuery, GlError> {
+        let handle = gl.context2().ok_or(GlError::WebGl2Required)?.create_query().unwrap();
+        return Ok(OcclusionQuery {
+            data: Rc::new(OcclusionQueryInfo {
+                gl: gl,
+                handle: handle,
+            })
+        });
+    }
+
+    pub fn gl(&self) -> Gl {
+        self.data.gl.clone()
+    }
+
+    /**
+     * Count samples of the draws in the callback, replacing the previous result. Queries can't be nested.
+     */
+    pub fn measure<R>(&self, callback: impl FnOnce() -> R) -> R {
+        // The context is WebGL 2, `new` checked it
+        let context = self.data.gl.context2().unwrap();
+        context.begin_query(Context2::ANY_SAMPLES_PASSED, &self.data.handle);
+        let result = callback();
+        context.end_query(Context2::ANY_SAMPLES_PASSED);
+        return result;
+    }
+
+    /**
+     * Whether any samples passed during the last `measure`, `None` while the result is not available yet.
+     */
+    pub fn any_samples_passed(&self) -> Option<bool> {
+        let context = self.data.gl.context2().unwrap();
+        let available = context.get_query_parameter(&self.data.handle, Context2::QUERY_RESULT_AVAILABLE)
+            .as_bool()
+            .unwrap_or(false);
+        if !available {
+            return None;
+        }
+        // The result is a number even for boolean query targets
+        let result = context.get_query_parameter(&self.data.handle, Context2::QUERY_RESULT)
+            .as_f64()
+            .unwrap_or(0.0);
+        return Some(result != 0.0);
+    }
+}
@@ -0,0 +1,86 @@
+use super::gl::Gl;
+use super::settings::Settings;
+use super::settings::FramebufferSetting;
+use super::render_target::RenderTarget;
+
+/**
+ * Render pass drawing into a render target, or into the canvas when there is none.
+ */
+pub struct Pass {
+    pub(self) name: String,
+    pub(self) target: Option<RenderTarget>,
+    pub(self) draw: Box<dyn FnMut(&Gl)>,
+}
+
+impl std::fmt::Debug for Pass {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Pass")
+            .field("name", &self.name)
+            .field("target", &self.target)
+            .finish()
+    }
+}
+
+impl Pass {
+    pub fn new<F: FnMut(&Gl) + 'static>(name: &str, target: Option<RenderTarget>, draw: F) -> Pass {
+        Pass {
+            name: name.to_string(),
+            target: target,
+            draw: Box::new(draw),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn target(&self) -> Option<&RenderTarget> {
+        self.target.as_ref()
+    }
+}
+
+/**
+ * Ordered list of passes. Each pass runs with its target bound and the viewport covering it,
+ * everything is restored after the pass so state set by one pass doesn't leak into the next.
+ * Passes run in the order they were added, a pass reading another's output must come after it.
+ */
+#[derive(Debug)]
+pub struct RenderGraph {
+    pub(self) gl: Gl,
+    pub(self) passes: Vec<Pass>,
+}
+
+impl RenderGraph {
+    pub fn new(gl: Gl) -> RenderGraph {
+        RenderGraph {
+            gl: gl,
+            passes: Vec::new(),
+        }
+    }
+
+    pub fn add_pass(&mut self, pass: Pass) {
+        self.passes.push(pass);
+    }
+
+    pub fn passes(&self) -> &[Pass] {
+        &self.passes
+    }
+
+    pub fn execute(&mut self) {
+        let ref gl = self.gl;
+        for pass in self.passes.iter_mut() {
+            let (width, height) = match pass.target.as_ref() {
+                Some(target) => target.size(),
+                None => (gl.context().drawing_buffer_width() as u32, gl.context().drawing_buffer_height() as u32),
+            };
+            let framebuffer = FramebufferSetting(pass.target.as_ref().map(|target| target.framebuffer()));
+            let draw = &mut pass.draw;
+            gl.apply(
+                Gl::settings()
+                    .with(framebuffer)
+                    .viewport(0, 0, width as i32, height as i32),
+                || draw(gl)
+            );
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::mem;
+
+use super::gl::Gl;
+use super::gl::Capability;
+use super::error::GlError;
+use super::framebuffer::Framebuffer;
+use super::texture::Texture;
+use super::texture::TextureType;
+use super::texture::TextureFormat;
+use super::texture::TextureContent;
+use super::texture::TextureFilter;
+use super::texture::TextureWrap;
+
+/**
+ * Framebuffer with its own color texture of the given size. Render into it with
+ * `Gl::settings().framebuffer(target.framebuffer())` and sample `target.texture()` afterwards.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RenderTarget {
+    pub(self) framebuffer: Framebuffer,
+}
+
+impl RenderTarget {
+    pub fn new(gl: Gl, width: u32, height: u32, data_type: TextureType, format: TextureFormat) -> Result<RenderTarget, GlError> {
+        let color = Texture::new(gl.clone(), width, height, data_type, format, TextureContent::None);
+        return Ok(RenderTarget {
+            framebuffer: Framebuffer::new(gl, color)?,
+        });
+    }
+
+    /**
+     * Target `scale` times the base size for downsampled passes like bloom, at least 1x1.
+     * The texture is filtered linearly without mipmaps and clamped to edge, so upsampling it is smooth
+     * and it samples correctly with any size on WebGL 1. Float targets without `OES_texture_float_linear`
+     * fall back to `Nearest` like `Texture::set_min_filter`, linear filtering would leave them incomplete.
+     */
+    pub fn scaled(gl: Gl, base_size: (u32, u32), scale: f32, data_type: TextureType, format: TextureFormat) -> Result<RenderTarget, GlError> {
+        let scaled = |size: u32| ((size as f32 * scale).round() as u32).max(1);
+        let (width, height) = (scaled(base_size.0), scaled(base_size.1));
+        let result = RenderTarget::new(gl.clone(), width, height, data_type, format)?;
+        let texture = result.texture();
+        let float_linear = data_type != TextureType::Float || gl.supports(Capability::FloatLinear);
+        texture.set_filter(if float_linear { TextureFilter::Linear } else { TextureFilter::Nearest });
+        texture.set_wrap(TextureWrap::ClampToEdge);
+        return Ok(result);
+    }
+
+    pub fn gl(&self) -> Gl {
+        self.framebuffer.gl()
+    }
+
+    pub fn framebuffer(&self) -> Framebuffer {
+        self.framebuffer.clone()
+    }
+
+    pub fn texture(&self) -> Texture {
+        self.framebuffer.color()
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.framebuffer.size()
+    }
+}
+
+/**
+ * Two render targets for feedback effects, shaders sample `read()` while rendering into `write()`
+ * and `swap()` exchanges them for the next step.
+ */
+#[derive(Clone, Debug)]
+pub struct PingPong {
+    pub(self) read: RenderTarget,
+    pub(self) write: RenderTarget,
+}
+
+impl PingPong {
+    pub fn new(gl: Gl, width: u32, height: u32, data_type: TextureType, format: TextureFormat) -> Result<PingPong, GlError> {
+        return Ok(PingPong {
+            read: RenderTarget::new(gl.clone(), width, height, data_type, format)?,
+            write: RenderTarget::new(gl, width, height, data_type, format)?,
+        });
+    }
+
+    /**
+     * Pair existing targets, they must not share the color texture since reading and writing
+     * the same attachment is undefined in GL.
+     */
+    pub fn from_targets(read: RenderTarget, write: RenderTarget) -> Result<PingPong, GlError> {
+        if read.texture() == write.texture() {
+            return Err(GlError::FeedbackLoop);
+        }
+        return Ok(PingPong {
+            read: read,
+            write: write,
+        });
+    }
+
+    pub fn read(&self) -> &RenderTarget {
+        &self.read
+    }
+
+    pub fn write(&self) -> &RenderTarget {
+        &self.write
+    }
+
+    pub fn swap(&mut self) {
+        mem::swap(&mut self.read, &mut self.write);
+    }
+}
@@ -0,0 +1,109 @@
+use std::rc::Rc;
+use web_sys::{
+    WebGlRenderbuffer,
+    WebGlRenderingContext as Context,
+};
+use num_enum::{
+    TryFromPrimitive,
+    IntoPrimitive,
+};
+
+use super::gl::Gl;
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, TryFromPrimitive, IntoPrimitive, PartialEq, Eq)]
+pub enum RenderbufferFormat {
+    /**
+     * 16 bit color, 4 bits per channel.
+     */
+    Rgba4 = Context::RGBA4,
+    /**
+     * 16 bit color, 5 bits per color channel and 1 bit alpha.
+     */
+    Rgb5A1 = Context::RGB5_A1,
+    /**
+     * 16 bit color without alpha, 6 bits for green.
+     */
+    Rgb565 = Context::RGB565,
+    DepthComponent16 = Context::DEPTH_COMPONENT16,
+    StencilIndex8 = Context::STENCIL_INDEX8,
+    DepthStencil = Context::DEPTH_STENCIL,
+}
+
+impl RenderbufferFormat {
+    /**
+     * Framebuffer attachment point the format is renderable at.
+     */
+    pub fn attachment(&self) -> u32 {
+        match self {
+            RenderbufferFormat::Rgba4 | RenderbufferFormat::Rgb5A1 | RenderbufferFormat::Rgb565 => Context::COLOR_ATTACHMENT0,
+            RenderbufferFormat::DepthComponent16 => Context::DEPTH_ATTACHMENT,
+            RenderbufferFormat::StencilIndex8 => Context::STENCIL_ATTACHMENT,
+            RenderbufferFormat::DepthStencil => Context::DEPTH_STENCIL_ATTACHMENT,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RenderbufferInfo {
+    pub(self) gl: Gl,
+    pub(super) handle: WebGlRenderbuffer,
+    pub(self) width: u32,
+    pub(self) height: u32,
+    pub(self) format: RenderbufferFormat,
+}
+
+impl Drop for RenderbufferInfo {
+    fn drop(&mut self) {
+        self.gl.context().delete_renderbuffer(Some(&self.handle));
+    }
+}
+
+/**
+ * Render only storage, cheaper than a texture when the content is never sampled.
+ * Attach it with `Framebuffer::attach_renderbuffer`.
+ */
+#[derive(Clone, Debug)]
+pub struct Renderbuffer {
+    pub(super) data: Rc<RenderbufferInfo>,
+}
+
+impl PartialEq<Renderbuffer> for Renderbuffer {
+    fn eq(&self, other: &Renderbuffer) -> bool {
+        self.data.handle == other.data.handle
+    }
+}
+
+impl Eq for Renderbuffer {}
+
+impl Renderbuffer {
+    pub fn new(gl: Gl, width: u32, height: u32, format: RenderbufferFormat) -> Renderbuffer {
+        let ref context = gl.context();
+        let handle = context.create_renderbuffer().unwrap();
+        context.bind_renderbuffer(Context::RENDERBUFFER, Some(&handle));
+        context.renderbuffer_storage(Context::RENDERBUFFER, format.into(), width as i32, height as i32);
+        context.bind_renderbuffer(Context::RENDERBUFFER, None);
+
+        return Renderbuffer {
+            data: Rc::new(RenderbufferInfo {
+                gl: gl.clone(),
+                handle: handle,
+                width: width,
+                height: height,
+                format: format,
+            }),
+        };
+    }
+
+    pub fn gl(&self) -> Gl {
+        self.data.gl.clone()
+    }
+
+    pub fn format(&self) -> RenderbufferFormat {
+        self.data.format
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.data.width, self.data.height)
+    }
+}
@@ -0,0 +1,97 @@
+use std::rc::Rc;
+use std::cell::Cell;
+use web_sys::{
+    WebGlSampler,
+    WebGl2RenderingContext as Context2,
+};
+
+use super::gl::Gl;
+use super::error::GlError;
+use super::texture::TextureFilter;
+use super::texture::TextureWrap;
+
+#[derive(Debug)]
+pub struct SamplerInfo {
+    pub(self) gl: Gl,
+    pub(super) handle: WebGlSampler,
+    pub(self) filter: Cell<TextureFilter>,
+    pub(self) wrap: Cell<TextureWrap>,
+}
+
+impl PartialEq<SamplerInfo> for SamplerInfo {
+    fn eq(&self, other: &SamplerInfo) -> bool {
+        self.handle == other.handle
+    }
+}
+
+impl Eq for SamplerInfo {}
+
+impl Drop for SamplerInfo {
+    fn drop(&mut self) {
+        if let Some(context) = self.gl.context2() {
+            context.delete_sampler(Some(&self.handle));
+        }
+    }
+}
+
+/**
+ * WebGL 2 sampler object. While bound to a texture unit it overrides the filter and wrap of the texture on that unit.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sampler {
+    pub(super) data: Rc<SamplerInfo>,
+}
+
+impl Sampler {
+    pub fn new(gl: Gl) -> Result<Sampler, GlError> {
+        let context = gl.context2().ok_or(GlError::WebGl2Required)?;
+        let handle = context.create_sampler().unwrap();
+        let filter = TextureFilter::default();
+        let wrap = TextureWrap::default();
+        context.sampler_parameteri(&handle, Context2::TEXTURE_MAG_FILTER, filter.into());
+        context.sampler_parameteri(&handle, Context2::TEXTURE_MIN_FILTER, filter.into());
+        context.sampler_parameteri(&handle, Context2::TEXTURE_WRAP_S, wrap.into());
+        context.sampler_parameteri(&handle, Context2::TEXTURE_WRAP_T, wrap.into());
+
+        return Ok(Sampler {
+            data: Rc::new(SamplerInfo {
+                gl: gl.clone(),
+                handle: handle,
+                filter: Cell::new(filter),
+                wrap: Cell::new(wrap),
+            }),
+        });
+    }
+
+    pub fn gl(&self) -> Gl {
+        self.data.gl.clone()
+    }
+
+    pub fn filter(&self) -> TextureFilter {
+        self.data.filter.get()
+    }
+
+    pub fn set_filter(&self, filter: TextureFilter) {
+        if self.filter() != filter {
+            if let Some(context) = self.data.gl.context2() {
+                context.sampler_parameteri(&self.data.handle, Context2::TEXTURE_MAG_FILTER, filter.into());
+                context.sampler_parameteri(&self.data.handle, Context2::TEXTURE_MIN_FILTER, filter.into());
+                self.data.filter.set(filter);
+            }
+        }
+    }
+
+    pub fn wrap(&self) -> TextureWrap {
+        self.data.wrap.get()
+    }
+
+    pub fn set_wrap(&self, wrap: TextureWrap) {
+        if self.wrap() != wrap {
+            if let Some(context) = self.data.gl.context2() {
+                context.sampler_parameteri(&self.data.handle, Context2::TEXTURE_WRAP_S, wrap.into());
+                context.sampler_parameteri(&self.data.handle, Context2::TEXTURE_WRAP_T, wrap.into());
+                self.data.wrap.set(wrap);
+            }
+        }
+    }
+}
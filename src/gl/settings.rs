@@ -2,20 +2,378 @@ use std::fmt::Debug;
 use std::ops::DerefMut;
 use std::ops::Deref;
 use std::cell::RefCell;
+use std::rc::Rc;
+use std::rc::Weak;
+use std::convert::TryFrom;
+use wasm_bindgen::JsCast;
+use js_sys::Int32Array;
+use js_sys::Float32Array;
+use js_sys::Array;
+use web_sys::console;
 use web_sys::WebGlRenderingContext as Context;
+use web_sys::WebGl2RenderingContext as Context2;
+use num_enum::{
+    TryFromPrimitive,
+    IntoPrimitive,
+};
 
 use super::gl::Gl;
 use super::texture::Texture;
+use super::texture::TextureInfo;
 use super::texture::TextureFilter;
+use super::texture_array::TextureArray;
+use super::texture_array::TextureArrayInfo;
 use super::data_buffer::ArrayBuffer;
+use super::data_buffer::ArrayBufferData;
+use super::data_buffer::ElementBuffer;
+use super::data_buffer::ElementBufferData;
+use super::sampler::Sampler;
+use super::program::Program;
+use super::framebuffer::Framebuffer;
+use super::framebuffer::FramebufferInfo;
+use super::attribute::AttributeLayout;
+use super::color::Color;
 
 #[derive(Clone, Debug, Default)]
 pub struct SettingsCache {
     blend: BlendSetting,
+    blend_func: BlendFuncSeparateSetting,
+    blend_color: BlendColorSetting,
     depth: DepthTestSetting,
+    depth_mask: DepthMaskSetting,
+    depth_range: DepthRangeSetting,
+    dither: DitherSetting,
+    sample_coverage: SampleCoverageSetting,
+    stencil_test: StencilTestSetting,
+    stencil_func: StencilFuncSetting,
+    stencil_op: StencilOpSetting,
+    stencil_mask: StencilMaskSetting,
+    color_mask: ColorMaskSetting,
+    scissor: ScissorSetting,
+    viewport: ViewportSetting,
+    clear_color: ClearColorSetting,
+    colorspace_conversion: ColorspaceConversionSetting,
+    mipmap_hint: MipmapHintSetting,
+    unpack_region: UnpackRegionSetting,
     active_texture: ActiveTextureSetting,
-    array_buffer: ArrayBufferSetting,
-    textures: [Option<Texture>; 16],
+    program: ProgramSetting,
+    /**
+     * Buffers are tracked weakly like textures, a dropped buffer is deleted and GL unbinds it.
+     */
+    array_buffer: Option<Weak<ArrayBufferData>>,
+    element_buffer: Option<Weak<ElementBufferData>>,
+    /**
+     * Sized to `MAX_VERTEX_ATTRIBS`, which is at least 16 but can be more.
+     */
+    attributes: Vec<Option<AttributeSetting>>,
+    /**
+     * Bound textures are tracked weakly, the cache must not keep a texture dropped by the user alive.
+     * Sized to `MAX_COMBINED_TEXTURE_IMAGE_UNITS`, like `samplers`.
+     */
+    textures: Vec<Option<Weak<TextureInfo>>>,
+    texture_arrays: Vec<Option<Weak<TextureArrayInfo>>>,
+    samplers: Vec<Option<Sampler>>,
+    /**
+     * Tracked weakly for the same reason as textures, `None` is the canvas.
+     */
+    framebuffer: Option<Weak<FramebufferInfo>>,
+}
+
+impl SettingsCache {
+    /**
+     * Cache for a new context. The viewport is taken from GL, it's the canvas size at creation rather than zero.
+     */
+    pub(super) fn for_context(context: &Context, units: u32) -> SettingsCache {
+        let mut result = SettingsCache::default();
+        if let Some(viewport) = context.get_parameter(Context::VIEWPORT).ok()
+            .and_then(|value| value.dyn_into::<Int32Array>().ok())
+        {
+            let mut values = [0; 4];
+            viewport.copy_to(&mut values);
+            result.viewport = ViewportSetting(values[0], values[1], values[2], values[3]);
+        }
+        let attributes = context.get_parameter(Context::MAX_VERTEX_ATTRIBS).ok()
+            .and_then(|value| value.as_f64())
+            .map_or(16, |value| value as usize);
+        result.attributes.resize(attributes, None);
+        result.textures.resize(units as usize, None);
+        result.texture_arrays.resize(units as usize, None);
+        result.samplers.resize(units as usize, None);
+        return result;
+    }
+
+    /**
+     * Take capabilities, functions, masks and pixel store values set by foreign code from GL, for contexts
+     * created outside of the crate. Bound objects weren't created by the crate, they can't be tracked
+     * and are assumed unbound.
+     */
+    pub(super) fn read_context_state(&mut self, context: &Context) {
+        self.blend = BlendSetting(context.is_enabled(Context::BLEND));
+        self.depth = DepthTestSetting(context.is_enabled(Context::DEPTH_TEST));
+        self.depth_mask = DepthMaskSetting(
+            context.get_parameter(Context::DEPTH_WRITEMASK).ok().and_then(|value| value.as_bool()).unwrap_or(true)
+        );
+        self.stencil_test = StencilTestSetting(context.is_enabled(Context::STENCIL_TEST));
+        self.dither = DitherSetting(context.is_enabled(Context::DITHER));
+        self.scissor = ScissorSetting(None);
+        if context.is_enabled(Context::SCISSOR_TEST) {
+            if let Some(scissor) = context.get_parameter(Context::SCISSOR_BOX).ok()
+                .and_then(|value| value.dyn_into::<Int32Array>().ok())
+            {
+                let mut values = [0; 4];
+                scissor.copy_to(&mut values);
+                self.scissor = ScissorSetting(Some((values[0], values[1], values[2], values[3])));
+            }
+        }
+        if let Some(clear_color) = context.get_parameter(Context::COLOR_CLEAR_VALUE).ok()
+            .and_then(|value| value.dyn_into::<Float32Array>().ok())
+        {
+            let mut values = [0.0; 4];
+            clear_color.copy_to(&mut values);
+            self.clear_color = ClearColorSetting(Color::from(values));
+        }
+        if let Some(active_texture) = context.get_parameter(Context::ACTIVE_TEXTURE).ok()
+            .and_then(|value| value.as_f64())
+        {
+            self.active_texture = ActiveTextureSetting(active_texture as u32 - Context::TEXTURE0);
+        }
+
+        let number = |name: u32| context.get_parameter(name).ok().and_then(|value| value.as_f64());
+        let boolean = |name: u32| context.get_parameter(name).ok().and_then(|value| value.as_bool());
+        let floats = |name: u32| context.get_parameter(name).ok()
+            .and_then(|value| value.dyn_into::<Float32Array>().ok())
+            .map(|array| array.to_vec());
+        let factor = |name: u32| number(name).and_then(|value| BlendFactor::try_from(value as u32).ok());
+
+        if let (Some(src_rgb), Some(dst_rgb), Some(src_alpha), Some(dst_alpha)) = (
+            factor(Context::BLEND_SRC_RGB),
+            factor(Context::BLEND_DST_RGB),
+            factor(Context::BLEND_SRC_ALPHA),
+            factor(Context::BLEND_DST_ALPHA),
+        ) {
+            self.blend_func = BlendFuncSeparateSetting {
+                src_rgb: src_rgb,
+                dst_rgb: dst_rgb,
+                src_alpha: src_alpha,
+                dst_alpha: dst_alpha,
+            };
+        }
+        if let Some(values) = floats(Context::BLEND_COLOR).filter(|values| values.len() == 4) {
+            self.blend_color = BlendColorSetting(Color::from([values[0], values[1], values[2], values[3]]));
+        }
+        if let Some(values) = floats(Context::DEPTH_RANGE).filter(|values| values.len() == 2) {
+            self.depth_range = DepthRangeSetting {
+                near: values[0],
+                far: values[1],
+            };
+        }
+        self.sample_coverage = SampleCoverageSetting {
+            alpha_to_coverage: context.is_enabled(Context::SAMPLE_ALPHA_TO_COVERAGE),
+            coverage: if context.is_enabled(Context::SAMPLE_COVERAGE) {
+                Some((
+                    number(Context::SAMPLE_COVERAGE_VALUE).unwrap_or(1.0) as f32,
+                    boolean(Context::SAMPLE_COVERAGE_INVERT).unwrap_or(false),
+                ))
+            } else {
+                None
+            },
+        };
+        if let (Some(func), Some(reference), Some(mask)) = (
+            number(Context::STENCIL_FUNC).and_then(|value| CompareFunction::try_from(value as u32).ok()),
+            number(Context::STENCIL_REF),
+            number(Context::STENCIL_VALUE_MASK),
+        ) {
+            self.stencil_func = StencilFuncSetting {
+                func: func,
+                reference: reference as i32,
+                mask: mask as u32,
+            };
+        }
+        let stencil_op = |name: u32| number(name).and_then(|value| StencilOp::try_from(value as u32).ok());
+        if let (Some(fail), Some(z_fail), Some(z_pass)) = (
+            stencil_op(Context::STENCIL_FAIL),
+            stencil_op(Context::STENCIL_PASS_DEPTH_FAIL),
+            stencil_op(Context::STENCIL_PASS_DEPTH_PASS),
+        ) {
+            self.stencil_op = StencilOpSetting {
+                fail: fail,
+                z_fail: z_fail,
+                z_pass: z_pass,
+            };
+        }
+        if let Some(mask) = number(Context::STENCIL_WRITEMASK) {
+            self.stencil_mask = StencilMaskSetting(mask as u32);
+        }
+        if let Some(mask) = context.get_parameter(Context::COLOR_WRITEMASK).ok()
+            .and_then(|value| value.dyn_into::<Array>().ok())
+        {
+            let channel = |i: u32| mask.get(i).as_bool().unwrap_or(true);
+            self.color_mask = ColorMaskSetting([channel(0), channel(1), channel(2), channel(3)]);
+        }
+        if let Some(conversion) = number(Context::UNPACK_COLORSPACE_CONVERSION_WEBGL) {
+            self.colorspace_conversion = ColorspaceConversionSetting(conversion as u32 == Context::BROWSER_DEFAULT_WEBGL);
+        }
+        if let Some(quality) = number(Context::GENERATE_MIPMAP_HINT).and_then(|value| Quality::try_from(value as u32).ok()) {
+            self.mipmap_hint = MipmapHintSetting(quality);
+        }
+        // WebGL 1 doesn't know the parameters, querying them would leave an INVALID_ENUM error
+        if !context.has_type::<Context2>() {
+            return;
+        }
+        if let (Some(row_length), Some(skip_pixels), Some(skip_rows)) = (
+            number(Context2::UNPACK_ROW_LENGTH),
+            number(Context2::UNPACK_SKIP_PIXELS),
+            number(Context2::UNPACK_SKIP_ROWS),
+        ) {
+            self.unpack_region = UnpackRegionSetting {
+                row_length: row_length as u32,
+                skip_pixels: skip_pixels as u32,
+                skip_rows: skip_rows as u32,
+            };
+        }
+    }
+
+    /**
+     * Forget bindings of textures that were dropped. GL unbinds a texture itself when it's deleted.
+     */
+    pub(super) fn evict_dropped_textures(&mut self) {
+        for texture in self.textures.iter_mut() {
+            if texture.as_ref().map_or(false, |texture| texture.upgrade().is_none()) {
+                *texture = None;
+            }
+        }
+        for texture_array in self.texture_arrays.iter_mut() {
+            if texture_array.as_ref().map_or(false, |texture_array| texture_array.upgrade().is_none()) {
+                *texture_array = None;
+            }
+        }
+    }
+
+    /**
+     * Forget all the attribute bindings, used after the arrays were disabled behind the cache's back.
+     */
+    pub(super) fn reset_attributes(&mut self) {
+        for attribute in self.attributes.iter_mut() {
+            *attribute = None;
+        }
+    }
+
+    pub(super) fn reset_attribute(&mut self, location: u32) {
+        if let Some(attribute) = self.attributes.get_mut(location as usize) {
+            *attribute = None;
+        }
+    }
+
+    /**
+     * Forget the bound array buffer if it was dropped, returns true if it was.
+     */
+    pub(super) fn evict_dropped_array_buffer(&mut self) -> bool {
+        if self.array_buffer.as_ref().map_or(false, |buffer| buffer.upgrade().is_none()) {
+            self.array_buffer = None;
+            return true;
+        }
+        return false;
+    }
+
+    /**
+     * Forget the bound element buffer if it was dropped, returns true if it was.
+     */
+    pub(super) fn evict_dropped_element_buffer(&mut self) -> bool {
+        if self.element_buffer.as_ref().map_or(false, |buffer| buffer.upgrade().is_none()) {
+            self.element_buffer = None;
+            return true;
+        }
+        return false;
+    }
+
+    /**
+     * Framebuffer bound according to the cache, `None` is the canvas.
+     */
+    pub(super) fn framebuffer(&self) -> Option<Framebuffer> {
+        self.framebuffer
+            .as_ref()
+            .and_then(|framebuffer| framebuffer.upgrade())
+            .map(|data| Framebuffer { data: data })
+    }
+
+    /**
+     * Texture unit the color attachment of the bound framebuffer is also bound to, if any.
+     * Sampling the texture being rendered to is a feedback loop with undefined results.
+     */
+    pub(super) fn feedback_loop_unit(&self) -> Option<u32> {
+        let color = self.framebuffer()?.color();
+        return self.textures.iter()
+            .position(|texture| texture.as_ref().map_or(false, |texture| texture.ptr_eq(&Rc::downgrade(&color.data))))
+            .map(|unit| unit as u32);
+    }
+
+    /**
+     * Forget the bound framebuffer if it was dropped, returns true if it was.
+     */
+    pub(super) fn evict_dropped_framebuffer(&mut self) -> bool {
+        if self.framebuffer.as_ref().map_or(false, |framebuffer| framebuffer.upgrade().is_none()) {
+            self.framebuffer = None;
+            return true;
+        }
+        return false;
+    }
+
+    /**
+     * Issue GL calls for every cached value, so GL matches the cache again after foreign code changed it.
+     */
+    pub(super) fn write_all(gl: &Gl, cache: &RefCell<SettingsCache>) {
+        let state = cache.borrow().clone();
+
+        BlendSetting::set(gl, &state.blend);
+        BlendFuncSeparateSetting::set(gl, &state.blend_func);
+        BlendColorSetting::set(gl, &state.blend_color);
+        DepthTestSetting::set(gl, &state.depth);
+        DepthMaskSetting::set(gl, &state.depth_mask);
+        DepthRangeSetting::set(gl, &state.depth_range);
+        DitherSetting::set(gl, &state.dither);
+        SampleCoverageSetting::set(gl, &state.sample_coverage);
+        StencilTestSetting::set(gl, &state.stencil_test);
+        StencilFuncSetting::set(gl, &state.stencil_func);
+        StencilOpSetting::set(gl, &state.stencil_op);
+        StencilMaskSetting::set(gl, &state.stencil_mask);
+        ColorMaskSetting::set(gl, &state.color_mask);
+        ScissorSetting::set(gl, &state.scissor);
+        ViewportSetting::set(gl, &state.viewport);
+        ClearColorSetting::set(gl, &state.clear_color);
+        ColorspaceConversionSetting::set(gl, &state.colorspace_conversion);
+        MipmapHintSetting::set(gl, &state.mipmap_hint);
+        UnpackRegionSetting::set(gl, &state.unpack_region);
+
+        for unit in 0..state.textures.len() {
+            ActiveTextureSetting::set(gl, &ActiveTextureSetting(unit as u32));
+            let texture = state.textures[unit]
+                .as_ref()
+                .and_then(|texture| texture.upgrade())
+                .map(|data| Texture { data: data });
+            TextureSetting::set_texture(gl, texture.as_ref());
+            let texture_array = state.texture_arrays[unit]
+                .as_ref()
+                .and_then(|texture_array| texture_array.upgrade())
+                .map(|data| TextureArray { data: data });
+            TextureArraySetting::set_texture_array(gl, texture_array.as_ref());
+            SamplerSetting::set_sampler(gl, unit as u32, state.samplers[unit].as_ref());
+        }
+        ActiveTextureSetting::set(gl, &state.active_texture);
+
+        let framebuffer = state.framebuffer
+            .as_ref()
+            .and_then(|framebuffer| framebuffer.upgrade())
+            .map(|data| Framebuffer { data: data });
+        FramebufferSetting::set_framebuffer(gl, framebuffer.as_ref());
+        ProgramSetting::set(gl, &state.program);
+        ArrayBufferSetting::set(gl, &ArrayBufferSetting::get_cached(&cache.borrow()));
+        ElementBufferSetting::set(gl, &ElementBufferSetting::get_cached(&cache.borrow()));
+
+        let locations = std::cmp::min(state.attributes.len(), gl.max_vertex_attribs() as usize);
+        for location in 0..locations {
+            AttributeSetting::set_attribute(gl, cache, location as u32, state.attributes[location].as_ref());
+        }
+    }
 }
 
 pub trait Settings
@@ -30,10 +388,182 @@ where
         ComposedSetting(self, DepthTestSetting(value))
     }
 
+    /**
+     * Whether passing fragments write their depth, on by default.
+     */
+    fn depth_mask(self, value: bool) -> ComposedSetting<Self, DepthMaskSetting> {
+        ComposedSetting(self, DepthMaskSetting(value))
+    }
+
+    /**
+     * Canonical state for transparent geometry drawn back to front after the opaque one: straight alpha
+     * blending, depth test on so opaque geometry hides it, and depth writes off so transparent surfaces
+     * don't hide each other.
+     */
+    fn transparent(self) -> ComposedSetting<ComposedSetting<ComposedSetting<ComposedSetting<Self,
+        BlendSetting>, BlendFuncSetting>, DepthTestSetting>, DepthMaskSetting>
+    {
+        self.blend(true)
+            .blend_func(BlendFactor::SrcAlpha, BlendFactor::OneMinusSrcAlpha)
+            .depth_test(true)
+            .depth_mask(false)
+    }
+
+    /**
+     * Map normalized device depth to the window depth range, values are clamped to 0..1.
+     */
+    fn depth_range(self, near: f32, far: f32) -> ComposedSetting<Self, DepthRangeSetting> {
+        ComposedSetting(self, DepthRangeSetting {
+            near: near.max(0.0).min(1.0),
+            far: far.max(0.0).min(1.0),
+        })
+    }
+
+    /**
+     * Dithering of color output, on by default. Disable it for pixel exact rendering into textures.
+     */
+    fn dither(self, value: bool) -> ComposedSetting<Self, DitherSetting> {
+        ComposedSetting(self, DitherSetting(value))
+    }
+
+    /**
+     * Multisample coverage: alpha to coverage and an optional `(value, invert)` coverage mask.
+     */
+    fn sample_coverage(self, alpha_to_coverage: bool, coverage: Option<(f32, bool)>) -> ComposedSetting<Self, SampleCoverageSetting> {
+        ComposedSetting(self, SampleCoverageSetting {
+            alpha_to_coverage: alpha_to_coverage,
+            coverage: coverage,
+        })
+    }
+
+    fn stencil_test(self, value: bool) -> ComposedSetting<Self, StencilTestSetting> {
+        ComposedSetting(self, StencilTestSetting(value))
+    }
+
+    fn stencil_func(self, func: CompareFunction, reference: i32, mask: u32) -> ComposedSetting<Self, StencilFuncSetting> {
+        ComposedSetting(self, StencilFuncSetting {
+            func: func,
+            reference: reference,
+            mask: mask,
+        })
+    }
+
+    fn stencil_op(self, fail: StencilOp, z_fail: StencilOp, z_pass: StencilOp) -> ComposedSetting<Self, StencilOpSetting> {
+        ComposedSetting(self, StencilOpSetting {
+            fail: fail,
+            z_fail: z_fail,
+            z_pass: z_pass,
+        })
+    }
+
+    /**
+     * Bits of the stencil buffer that can be written.
+     */
+    fn stencil_mask(self, mask: u32) -> ComposedSetting<Self, StencilMaskSetting> {
+        ComposedSetting(self, StencilMaskSetting(mask))
+    }
+
+    fn color_mask(self, red: bool, green: bool, blue: bool, alpha: bool) -> ComposedSetting<Self, ColorMaskSetting> {
+        ComposedSetting(self, ColorMaskSetting([red, green, blue, alpha]))
+    }
+
+    /**
+     * Enable scissor test with the rectangle, in pixels from the bottom left corner.
+     */
+    fn scissor(self, x: i32, y: i32, width: i32, height: i32) -> ComposedSetting<Self, ScissorSetting> {
+        ComposedSetting(self, ScissorSetting(Some((x, y, width, height))))
+    }
+
+    /**
+     * Rectangle the normalized device coordinates map to, in pixels from the bottom left corner.
+     */
+    fn viewport(self, x: i32, y: i32, width: i32, height: i32) -> ComposedSetting<Self, ViewportSetting> {
+        ComposedSetting(self, ViewportSetting(x, y, width, height))
+    }
+
+    /**
+     * Color used by `clear` calls made directly on the context. The value is passed unclamped, WebGL 1
+     * clamps it to 0..1, use `Gl::clear` on WebGL 2 for float attachments.
+     */
+    fn clear_color<C: Into<Color>>(self, color: C) -> ComposedSetting<Self, ClearColorSetting> {
+        ComposedSetting(self, ClearColorSetting(color.into()))
+    }
+
+    /**
+     * Whether the browser converts the color space of uploaded images, disable it for data textures (normal maps, masks).
+     */
+    fn colorspace_conversion(self, enabled: bool) -> ComposedSetting<Self, ColorspaceConversionSetting> {
+        ComposedSetting(self, ColorspaceConversionSetting(enabled))
+    }
+
+    /**
+     * Quality of `Texture::generate_mipmap` downsampling.
+     */
+    fn mipmap_hint(self, quality: Quality) -> ComposedSetting<Self, MipmapHintSetting> {
+        ComposedSetting(self, MipmapHintSetting(quality))
+    }
+
+    /**
+     * Uploads read a `skip_pixels` x `skip_rows` offset region out of source rows `row_length` pixels long,
+     * e.g. to upload an atlas tile from a larger image with `Texture::write_sub_image`. WebGL 2 only,
+     * on WebGL 1 an error is logged and uploads read tightly packed data.
+     */
+    fn unpack_region(self, row_length: u32, skip_pixels: u32, skip_rows: u32) -> ComposedSetting<Self, UnpackRegionSetting> {
+        ComposedSetting(self, UnpackRegionSetting {
+            row_length: row_length,
+            skip_pixels: skip_pixels,
+            skip_rows: skip_rows,
+        })
+    }
+
     fn blend(self, value: bool) -> ComposedSetting<Self, BlendSetting> {
         ComposedSetting(self, BlendSetting(value))
     }
 
+    fn blend_func(self, src: BlendFactor, dst: BlendFactor) -> ComposedSetting<Self, BlendFuncSetting> {
+        ComposedSetting(self, BlendFuncSetting {
+            src: src,
+            dst: dst,
+        })
+    }
+
+    fn blend_func_separate(
+        self,
+        src_rgb: BlendFactor,
+        dst_rgb: BlendFactor,
+        src_alpha: BlendFactor,
+        dst_alpha: BlendFactor,
+    ) -> ComposedSetting<Self, BlendFuncSeparateSetting> {
+        ComposedSetting(self, BlendFuncSeparateSetting {
+            src_rgb: src_rgb,
+            dst_rgb: dst_rgb,
+            src_alpha: src_alpha,
+            dst_alpha: dst_alpha,
+        })
+    }
+
+    /**
+     * Color used by the `Constant*` blend factors.
+     */
+    fn blend_color<C: Into<Color>>(self, color: C) -> ComposedSetting<Self, BlendColorSetting> {
+        ComposedSetting(self, BlendColorSetting(color.into()))
+    }
+
+    /**
+     * Append an already constructed setting, e.g. `BlendFuncSetting::premultiplied()`.
+     */
+    fn with<S: Settings>(self, setting: S) -> ComposedSetting<Self, S> {
+        ComposedSetting(self, setting)
+    }
+
+    /**
+     * Append a setting conditionally, `None` leaves the state untouched.
+     * The resulting type is the same in both cases, so it can be chosen per object.
+     */
+    fn maybe<S: Settings>(self, setting: Option<S>) -> ComposedSetting<Self, OptionalSetting<S>> {
+        ComposedSetting(self, OptionalSetting(setting))
+    }
+
     fn texture(self, index: u32, texture: Texture) -> ComposedSetting<Self, TextureSetting> {
         ComposedSetting(self, TextureSetting {
             index: index,
@@ -41,6 +571,17 @@ where
         })
     }
 
+    /**
+     * Bind the WebGL 2 texture array to the unit, it's bound to `TEXTURE_2D_ARRAY` independently of
+     * a 2D texture on the same unit.
+     */
+    fn texture_array(self, index: u32, texture_array: TextureArray) -> ComposedSetting<Self, TextureArraySetting> {
+        ComposedSetting(self, TextureArraySetting {
+            index: index,
+            texture_array: Some(texture_array),
+        })
+    }
+
     fn texture_filter(self, texture: Texture, filter: TextureFilter) -> ComposedSetting<Self, TextureFilterSetting> {
         ComposedSetting(self, TextureFilterSetting {
             texture: texture,
@@ -48,15 +589,123 @@ where
         })
     }
 
+    fn texture_filters(self, filters: &[(Texture, TextureFilter)]) -> ComposedSetting<Self, TextureFiltersSetting> {
+        ComposedSetting(self, TextureFiltersSetting(filters.to_vec()))
+    }
+
+    /**
+     * Bind a sampler to the texture unit, WebGL 2 only.
+     */
+    fn sampler(self, unit: u32, sampler: Sampler) -> ComposedSetting<Self, SamplerSetting> {
+        ComposedSetting(self, SamplerSetting {
+            unit: unit,
+            sampler: Some(sampler),
+        })
+    }
+
+    /**
+     * Render into the framebuffer instead of the canvas.
+     */
+    fn framebuffer(self, framebuffer: Framebuffer) -> ComposedSetting<Self, FramebufferSetting> {
+        ComposedSetting(self, FramebufferSetting(Some(framebuffer)))
+    }
+
+    /**
+     * Render into the canvas, e.g. inside a scope that bound a framebuffer.
+     */
+    fn canvas(self) -> ComposedSetting<Self, FramebufferSetting> {
+        ComposedSetting(self, FramebufferSetting(None))
+    }
+
+    fn program(self, program: Program) -> ComposedSetting<Self, ProgramSetting> {
+        ComposedSetting(self, ProgramSetting(Some(program)))
+    }
+
     fn array_buffer(self, array_buffer: ArrayBuffer) -> ComposedSetting<Self, ArrayBufferSetting> {
         ComposedSetting(self, ArrayBufferSetting(Some(array_buffer)))
     }
+
+    fn element_buffer(self, element_buffer: ElementBuffer) -> ComposedSetting<Self, ElementBufferSetting> {
+        ComposedSetting(self, ElementBufferSetting(Some(element_buffer)))
+    }
+
+    /**
+     * Enable the vertex attribute array reading from the buffer, it's disabled again when the scope exits.
+     */
+    fn attribute(self, location: u32, buffer: ArrayBuffer, layout: AttributeLayout) -> ComposedSetting<Self, AttributeSetting> {
+        ComposedSetting(self, AttributeSetting::new(location, buffer, layout))
+    }
+
+    /**
+     * Several attribute bindings at once, e.g. from `VertexFormat::attributes`.
+     */
+    fn attributes(self, attributes: Vec<AttributeSetting>) -> ComposedSetting<Self, AttributesSetting> {
+        ComposedSetting(self, AttributesSetting(attributes))
+    }
+
+    /**
+     * Several texture bindings at once as `(unit, texture)` pairs, e.g. from `Program::bind_textures`.
+     */
+    fn textures(self, textures: Vec<(u32, Texture)>) -> ComposedSetting<Self, TexturesSetting> {
+        ComposedSetting(self, TexturesSetting(
+            textures.into_iter()
+                .map(|(index, texture)| TextureSetting {
+                    index: index,
+                    texture: Some(texture),
+                })
+                .collect()
+        ))
+    }
+
+    /**
+     * Unbind textures and texture arrays from every unit that has one bound, so nothing can be sampled
+     * from a texture being rendered to. The bindings are restored after the scope.
+     */
+    fn no_textures(self) -> ComposedSetting<Self, NoTexturesSetting> {
+        ComposedSetting(self, NoTexturesSetting)
+    }
+}
+
+/**
+ * Object safe counterpart of `Settings`, implemented for every setting, for lists of settings
+ * built at runtime like `Vec<Box<dyn DynSettings>>`. Apply them with `Gl::apply_all`.
+ */
+pub trait DynSettings: Debug {
+    fn apply_dyn(&self, gl: &Gl, cache: &RefCell<SettingsCache>, callback: &mut dyn FnMut());
+}
+
+impl <S: Settings> DynSettings for S {
+    fn apply_dyn(&self, gl: &Gl, cache: &RefCell<SettingsCache>, callback: &mut dyn FnMut()) {
+        self.apply(gl, cache, || callback())
+    }
+}
+
+/**
+ * Apply the settings in order, the first one is the outermost scope.
+ */
+pub(super) fn apply_dyn_from(
+    settings: &[Box<dyn DynSettings>],
+    gl: &Gl,
+    cache: &RefCell<SettingsCache>,
+    callback: &mut dyn FnMut(),
+) {
+    match settings.split_first() {
+        Some((first, rest)) => first.apply_dyn(gl, cache, &mut || apply_dyn_from(rest, gl, cache, callback)),
+        None => callback(),
+    }
 }
 
 pub trait CachedSettings {
     fn set(gl: &Gl, value: &Self);
     fn get_cached(cache: &impl Deref<Target = SettingsCache>) -> Self;
     fn set_cached(cache: &mut impl DerefMut<Target = SettingsCache>, value: &Self);
+    /**
+     * Whether the value can be used with the context, settings holding a GL object log an error when it
+     * belongs to another context and are then neither set nor cached.
+     */
+    fn check_owner(&self, _gl: &Gl) -> bool {
+        return true;
+    }
 }
 
 impl <T> Settings for T
@@ -68,8 +717,11 @@ where
 {
     fn apply<R, F: FnOnce() -> R>(&self, gl: &Gl, cache: &RefCell<SettingsCache>, callback: F) -> R
     {
+        if !self.check_owner(gl) {
+            return callback();
+        }
         let old_value = Self::get_cached(&cache.borrow());
-        return if self == &old_value {
+        return if self == &old_value && !gl.explicit_unbind() {
             callback()
         } else {
             Self::set_cached(&mut cache.borrow_mut(), self);
@@ -104,8 +756,43 @@ impl <S1: Settings, S2: Settings> Settings for ComposedSetting<S1, S2> {
     }
 }
 
+/**
+ * Settings of the same kind applied in order, the first one is the outermost scope.
+ */
+impl <S: Settings> Settings for Vec<S> {
+    fn apply<R, F: FnOnce() -> R>(&self, gl: &Gl, cache: &RefCell<SettingsCache>, callback: F) -> R
+    {
+        apply_from(self, gl, cache, callback)
+    }
+}
+
+pub(self) fn apply_from<S: Settings, R, F: FnOnce() -> R>(
+    settings: &[S],
+    gl: &Gl,
+    cache: &RefCell<SettingsCache>,
+    callback: F,
+) -> R {
+    match settings.split_first() {
+        Some((first, rest)) => first.apply(gl, cache, || apply_from(rest, gl, cache, callback)),
+        None => callback(),
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct OptionalSetting<S: Settings>(Option<S>);
+
+impl <S: Settings> Settings for OptionalSetting<S> {
+    fn apply<R, F: FnOnce() -> R>(&self, gl: &Gl, cache: &RefCell<SettingsCache>, callback: F) -> R
+    {
+        match &self.0 {
+            Some(setting) => setting.apply(gl, cache, callback),
+            None => callback(),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
-pub struct ActiveTextureSetting(u32);
+pub struct ActiveTextureSetting(pub(super) u32);
 
 impl CachedSettings for ActiveTextureSetting {
     fn set(gl: &Gl, value: &Self) {
@@ -120,100 +807,1087 @@ impl CachedSettings for ActiveTextureSetting {
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
-pub struct ArrayBufferSetting(Option<ArrayBuffer>);
+pub struct FramebufferSetting(pub(super) Option<Framebuffer>);
 
-impl CachedSettings for ArrayBufferSetting {
+impl FramebufferSetting {
+    pub(super) fn set_framebuffer(gl: &Gl, framebuffer: Option<&Framebuffer>) {
+        gl.context().bind_framebuffer(
+            Context::FRAMEBUFFER,
+            framebuffer
+                .filter(|framebuffer| !framebuffer.is_disposed())
+                .map(|framebuffer| framebuffer.data.handle.clone())
+                .as_ref()
+        );
+    }
+}
+
+impl Settings for FramebufferSetting {
+    fn apply<R, F: FnOnce() -> R>(&self, gl: &Gl, cache: &RefCell<SettingsCache>, callback: F) -> R {
+        if !self.0.as_ref().map_or(true, |framebuffer| gl.check_owner(&framebuffer.gl(), "Framebuffer")) {
+            return callback();
+        }
+        let previous = cache.borrow().framebuffer.clone();
+        let current = self.0.as_ref().map(|framebuffer| Rc::downgrade(&framebuffer.data));
+        let unchanged = match (&previous, &current) {
+            (Some(previous), Some(current)) => previous.ptr_eq(current),
+            (None, None) => true,
+            _ => false,
+        };
+        if unchanged && !gl.explicit_unbind() {
+            return callback();
+        }
+
+        cache.borrow_mut().framebuffer = current;
+        Self::set_framebuffer(gl, self.0.as_ref());
+        let result = callback();
+        // The previous framebuffer may have been dropped inside the callback, then the canvas is restored
+        let previous = previous.and_then(|previous| previous.upgrade()).map(|data| Framebuffer { data: data });
+        Self::set_framebuffer(gl, previous.as_ref());
+        cache.borrow_mut().framebuffer = previous.as_ref().map(|framebuffer| Rc::downgrade(&framebuffer.data));
+        return result;
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProgramSetting(Option<Program>);
+
+impl ProgramSetting {
+    /**
+     * Re-issue `use_program` if the program is the bound one, its GL handle changes on reload.
+     */
+    pub(super) fn refresh(gl: &Gl, program: &Program) {
+        let current = Self::get_cached(&gl.settings_cache().borrow());
+        if current.0.as_ref() == Some(program) {
+            Self::set(gl, &current);
+        }
+    }
+}
+
+impl CachedSettings for ProgramSetting {
     fn set(gl: &Gl, value: &Self) {
-        gl.context().bind_buffer(Context::ARRAY_BUFFER, value.0.as_ref().map(|v| v.handle()).as_ref());
+        gl.context().use_program(
+            value.0.as_ref()
+                .filter(|v| !v.is_disposed())
+                .map(|v| v.handle())
+                .as_ref()
+        );
     }
     fn get_cached(cache: &impl Deref<Target = SettingsCache>) -> Self {
-        cache.array_buffer.clone()
+        cache.program.clone()
     }
     fn set_cached(cache: &mut impl DerefMut<Target = SettingsCache>, value: &Self) {
-        cache.array_buffer = value.clone();
+        cache.program = value.clone();
+    }
+    fn check_owner(&self, gl: &Gl) -> bool {
+        return self.0.as_ref().map_or(true, |v| gl.check_owner(&v.gl(), "Program"));
     }
 }
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
-pub struct BlendSetting(bool);
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ArrayBufferSetting(Option<ArrayBuffer>);
 
-impl CachedSettings for BlendSetting {
+impl CachedSettings for ArrayBufferSetting {
     fn set(gl: &Gl, value: &Self) {
-        if value.0 {
-            gl.context().enable(Context::BLEND)
-        } else {
-            gl.context().disable(Context::BLEND)
-        }
+        gl.context().bind_buffer(
+            Context::ARRAY_BUFFER,
+            value.0.as_ref()
+                .filter(|v| !v.is_disposed())
+                .map(|v| v.handle())
+                .as_ref()
+        );
     }
     fn get_cached(cache: &impl Deref<Target = SettingsCache>) -> Self {
-        cache.blend
+        ArrayBufferSetting(
+            cache.array_buffer
+                .as_ref()
+                .and_then(|buffer| buffer.upgrade())
+                .map(|data| ArrayBuffer { data: data })
+        )
     }
     fn set_cached(cache: &mut impl DerefMut<Target = SettingsCache>, value: &Self) {
-        cache.blend = *value;
+        cache.array_buffer = value.0.as_ref().map(|buffer| Rc::downgrade(&buffer.data));
+    }
+    fn check_owner(&self, gl: &Gl) -> bool {
+        return self.0.as_ref().map_or(true, |v| gl.check_owner(&v.gl(), "Array buffer"));
     }
 }
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
-pub struct DepthTestSetting(bool);
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ElementBufferSetting(Option<ElementBuffer>);
 
-impl CachedSettings for DepthTestSetting {
+impl CachedSettings for ElementBufferSetting {
     fn set(gl: &Gl, value: &Self) {
-        if value.0 {
-            gl.context().enable(Context::DEPTH_TEST)
-        } else {
-            gl.context().disable(Context::DEPTH_TEST)
-        }
+        gl.context().bind_buffer(
+            Context::ELEMENT_ARRAY_BUFFER,
+            value.0.as_ref()
+                .filter(|v| !v.is_disposed())
+                .map(|v| v.handle())
+                .as_ref()
+        );
     }
     fn get_cached(cache: &impl Deref<Target = SettingsCache>) -> Self {
-        cache.depth
+        ElementBufferSetting(
+            cache.element_buffer
+                .as_ref()
+                .and_then(|buffer| buffer.upgrade())
+                .map(|data| ElementBuffer { data: data })
+        )
     }
     fn set_cached(cache: &mut impl DerefMut<Target = SettingsCache>, value: &Self) {
-        cache.depth = *value;
+        cache.element_buffer = value.0.as_ref().map(|buffer| Rc::downgrade(&buffer.data));
+    }
+    fn check_owner(&self, gl: &Gl) -> bool {
+        return self.0.as_ref().map_or(true, |v| gl.check_owner(&v.gl(), "Element buffer"));
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
-pub struct TextureSetting {
-    index: u32,
-    texture: Option<Texture>,
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AttributeSetting {
+    location: u32,
+    buffer: ArrayBuffer,
+    layout: AttributeLayout,
 }
 
-impl TextureSetting {
-    pub(self) fn set_texture(gl: &Gl, index: u32, texture: Option<&Texture>) {
-        gl.context().bind_texture(
-            Context::TEXTURE0 + index,
-            texture.map(|texture| texture.data.handle.clone()).as_ref()
-        );
+impl AttributeSetting {
+    pub fn new(location: u32, buffer: ArrayBuffer, layout: AttributeLayout) -> AttributeSetting {
+        AttributeSetting {
+            location: location,
+            buffer: buffer,
+            layout: layout,
+        }
+    }
+
+    pub(self) fn set_attribute(gl: &Gl, cache: &RefCell<SettingsCache>, location: u32, value: Option<&AttributeSetting>) {
+        match value {
+            Some(value) => {
+                let layout = value.layout;
+                ArrayBufferSetting(Some(value.buffer.clone())).apply(gl, cache, || {
+                    gl.context().enable_vertex_attrib_array(location);
+                    gl.context().vertex_attrib_pointer_with_i32(
+                        location,
+                        layout.size as i32,
+                        layout.data_type.into(),
+                        layout.normalized,
+                        layout.stride as i32,
+                        layout.offset as i32,
+                    );
+                });
+                gl.vertex_attrib_divisor(location, layout.divisor);
+            },
+            None => {
+                gl.context().disable_vertex_attrib_array(location);
+                gl.vertex_attrib_divisor(location, 0);
+            },
+        }
     }
 }
 
-impl Settings for TextureSetting {
+impl Settings for AttributeSetting {
     fn apply<R, F: FnOnce() -> R>(&self, gl: &Gl, cache: &RefCell<SettingsCache>, callback: F) -> R {
-        let previous = cache.borrow().textures[self.index as usize].clone();
-        cache.borrow_mut().textures[self.index as usize] = self.texture.clone();
-        Self::set_texture(gl, self.index, self.texture.as_ref());
+        let index = self.location as usize;
+        if index >= cache.borrow().attributes.len() {
+            console::error_1(&format!(
+                "Attribute location {} is out of range, the context has {} attributes, the attribute is not bound",
+                self.location, cache.borrow().attributes.len()
+            ).into());
+            return callback();
+        }
+        let previous = cache.borrow().attributes[index].clone();
+        if previous.as_ref() == Some(self) && !gl.explicit_unbind() {
+            return callback();
+        }
+        cache.borrow_mut().attributes[index] = Some(self.clone());
+        Self::set_attribute(gl, cache, self.location, Some(self));
         let result = callback();
-        Self::set_texture(gl, self.index, previous.as_ref());
-        cache.borrow_mut().textures[self.index as usize] = previous;
+        Self::set_attribute(gl, cache, self.location, previous.as_ref());
+        cache.borrow_mut().attributes[index] = previous;
         return result;
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub struct TextureFilterSetting {
-    texture: Texture,
-    filter: TextureFilter,
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AttributesSetting(Vec<AttributeSetting>);
+
+impl Settings for AttributesSetting {
+    fn apply<R, F: FnOnce() -> R>(&self, gl: &Gl, cache: &RefCell<SettingsCache>, callback: F) -> R {
+        self.0.apply(gl, cache, callback)
+    }
 }
 
-impl Settings for TextureFilterSetting {
-    fn apply<R, F: FnOnce() -> R>(&self, _: &Gl, _: &RefCell<SettingsCache>, callback: F) -> R {
-        let previous = self.texture.filter();
-        let current = self.filter;
-        self.texture.set_filter(current);
-        let result = callback();
-        self.texture.set_filter(previous);
-        return result;
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TexturesSetting(Vec<TextureSetting>);
+
+impl Settings for TexturesSetting {
+    fn apply<R, F: FnOnce() -> R>(&self, gl: &Gl, cache: &RefCell<SettingsCache>, callback: F) -> R {
+        self.0.apply(gl, cache, callback)
     }
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NoTexturesSetting;
+
+impl Settings for NoTexturesSetting {
+    fn apply<R, F: FnOnce() -> R>(&self, gl: &Gl, cache: &RefCell<SettingsCache>, callback: F) -> R {
+        // Only units bound according to the cache are touched, each restores its own binding
+        let (textures, texture_arrays) = {
+            let cache = cache.borrow();
+            let textures: Vec<TextureSetting> = (0..cache.textures.len())
+                .filter(|unit| cache.textures[*unit].as_ref().map_or(false, |texture| texture.upgrade().is_some()))
+                .map(|unit| TextureSetting { index: unit as u32, texture: None })
+                .collect();
+            let texture_arrays: Vec<TextureArraySetting> = (0..cache.texture_arrays.len())
+                .filter(|unit| cache.texture_arrays[*unit].as_ref().map_or(false, |texture_array| texture_array.upgrade().is_some()))
+                .map(|unit| TextureArraySetting { index: unit as u32, texture_array: None })
+                .collect();
+            (textures, texture_arrays)
+        };
+        textures.apply(gl, cache, || texture_arrays.apply(gl, cache, callback))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlendSetting(bool);
 
+impl CachedSettings for BlendSetting {
+    fn set(gl: &Gl, value: &Self) {
+        if value.0 {
+            gl.context().enable(Context::BLEND)
+        } else {
+            gl.context().disable(Context::BLEND)
+        }
+    }
+    fn get_cached(cache: &impl Deref<Target = SettingsCache>) -> Self {
+        cache.blend
+    }
+    fn set_cached(cache: &mut impl DerefMut<Target = SettingsCache>, value: &Self) {
+        cache.blend = *value;
+    }
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, TryFromPrimitive, IntoPrimitive, PartialEq, Eq)]
+pub enum BlendFactor {
+    Zero = Context::ZERO,
+    One = Context::ONE,
+    SrcColor = Context::SRC_COLOR,
+    OneMinusSrcColor = Context::ONE_MINUS_SRC_COLOR,
+    DstColor = Context::DST_COLOR,
+    OneMinusDstColor = Context::ONE_MINUS_DST_COLOR,
+    SrcAlpha = Context::SRC_ALPHA,
+    OneMinusSrcAlpha = Context::ONE_MINUS_SRC_ALPHA,
+    DstAlpha = Context::DST_ALPHA,
+    OneMinusDstAlpha = Context::ONE_MINUS_DST_ALPHA,
+    SrcAlphaSaturate = Context::SRC_ALPHA_SATURATE,
+    ConstantColor = Context::CONSTANT_COLOR,
+    OneMinusConstantColor = Context::ONE_MINUS_CONSTANT_COLOR,
+    ConstantAlpha = Context::CONSTANT_ALPHA,
+    OneMinusConstantAlpha = Context::ONE_MINUS_CONSTANT_ALPHA,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlendFuncSetting {
+    src: BlendFactor,
+    dst: BlendFactor,
+}
+
+impl Default for BlendFuncSetting {
+    fn default() -> Self {
+        BlendFuncSetting {
+            src: BlendFactor::One,
+            dst: BlendFactor::Zero,
+        }
+    }
+}
+
+impl BlendFuncSetting {
+    pub fn new(src: BlendFactor, dst: BlendFactor) -> BlendFuncSetting {
+        BlendFuncSetting {
+            src: src,
+            dst: dst,
+        }
+    }
+
+    /**
+     * Blending for straight (not premultiplied) alpha colors.
+     */
+    pub fn alpha_blend() -> BlendFuncSetting {
+        BlendFuncSetting::new(BlendFactor::SrcAlpha, BlendFactor::OneMinusSrcAlpha)
+    }
+
+    /**
+     * Blending for premultiplied alpha colors, matches the default `premultipliedAlpha: true` canvas compositing.
+     */
+    pub fn premultiplied() -> BlendFuncSetting {
+        BlendFuncSetting::new(BlendFactor::One, BlendFactor::OneMinusSrcAlpha)
+    }
+
+    pub fn additive() -> BlendFuncSetting {
+        BlendFuncSetting::new(BlendFactor::One, BlendFactor::One)
+    }
+}
+
+/**
+ * Same factors for color and alpha, a special case of `BlendFuncSeparateSetting` sharing its cached state.
+ */
+impl Settings for BlendFuncSetting {
+    fn apply<R, F: FnOnce() -> R>(&self, gl: &Gl, cache: &RefCell<SettingsCache>, callback: F) -> R {
+        BlendFuncSeparateSetting::from(*self).apply(gl, cache, callback)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlendFuncSeparateSetting {
+    src_rgb: BlendFactor,
+    dst_rgb: BlendFactor,
+    src_alpha: BlendFactor,
+    dst_alpha: BlendFactor,
+}
+
+impl Default for BlendFuncSeparateSetting {
+    fn default() -> Self {
+        BlendFuncSeparateSetting::from(BlendFuncSetting::default())
+    }
+}
+
+impl From<BlendFuncSetting> for BlendFuncSeparateSetting {
+    fn from(value: BlendFuncSetting) -> Self {
+        BlendFuncSeparateSetting {
+            src_rgb: value.src,
+            dst_rgb: value.dst,
+            src_alpha: value.src,
+            dst_alpha: value.dst,
+        }
+    }
+}
+
+impl BlendFuncSeparateSetting {
+    pub fn new(src_rgb: BlendFactor, dst_rgb: BlendFactor, src_alpha: BlendFactor, dst_alpha: BlendFactor) -> BlendFuncSeparateSetting {
+        BlendFuncSeparateSetting {
+            src_rgb: src_rgb,
+            dst_rgb: dst_rgb,
+            src_alpha: src_alpha,
+            dst_alpha: dst_alpha,
+        }
+    }
+}
+
+impl CachedSettings for BlendFuncSeparateSetting {
+    fn set(gl: &Gl, value: &Self) {
+        gl.context().blend_func_separate(
+            value.src_rgb.into(),
+            value.dst_rgb.into(),
+            value.src_alpha.into(),
+            value.dst_alpha.into(),
+        );
+    }
+    fn get_cached(cache: &impl Deref<Target = SettingsCache>) -> Self {
+        cache.blend_func
+    }
+    fn set_cached(cache: &mut impl DerefMut<Target = SettingsCache>, value: &Self) {
+        cache.blend_func = *value;
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BlendColorSetting(Color);
+
+impl CachedSettings for BlendColorSetting {
+    fn set(gl: &Gl, value: &Self) {
+        let Color { red, green, blue, alpha } = value.0;
+        gl.context().blend_color(red, green, blue, alpha);
+    }
+    fn get_cached(cache: &impl Deref<Target = SettingsCache>) -> Self {
+        cache.blend_color
+    }
+    fn set_cached(cache: &mut impl DerefMut<Target = SettingsCache>, value: &Self) {
+        cache.blend_color = *value;
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DepthTestSetting(bool);
+
+impl CachedSettings for DepthTestSetting {
+    fn set(gl: &Gl, value: &Self) {
+        if value.0 {
+            gl.context().enable(Context::DEPTH_TEST)
+        } else {
+            gl.context().disable(Context::DEPTH_TEST)
+        }
+    }
+    fn get_cached(cache: &impl Deref<Target = SettingsCache>) -> Self {
+        cache.depth
+    }
+    fn set_cached(cache: &mut impl DerefMut<Target = SettingsCache>, value: &Self) {
+        cache.depth = *value;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DepthMaskSetting(bool);
+
+impl Default for DepthMaskSetting {
+    fn default() -> Self {
+        DepthMaskSetting(true)
+    }
+}
+
+impl CachedSettings for DepthMaskSetting {
+    fn set(gl: &Gl, value: &Self) {
+        gl.context().depth_mask(value.0);
+    }
+    fn get_cached(cache: &impl Deref<Target = SettingsCache>) -> Self {
+        cache.depth_mask
+    }
+    fn set_cached(cache: &mut impl DerefMut<Target = SettingsCache>, value: &Self) {
+        cache.depth_mask = *value;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DepthRangeSetting {
+    near: f32,
+    far: f32,
+}
+
+impl Default for DepthRangeSetting {
+    fn default() -> Self {
+        DepthRangeSetting {
+            near: 0.0,
+            far: 1.0,
+        }
+    }
+}
+
+impl CachedSettings for DepthRangeSetting {
+    fn set(gl: &Gl, value: &Self) {
+        gl.context().depth_range(value.near, value.far);
+    }
+    fn get_cached(cache: &impl Deref<Target = SettingsCache>) -> Self {
+        cache.depth_range
+    }
+    fn set_cached(cache: &mut impl DerefMut<Target = SettingsCache>, value: &Self) {
+        cache.depth_range = *value;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DitherSetting(bool);
+
+impl Default for DitherSetting {
+    fn default() -> Self {
+        DitherSetting(true)
+    }
+}
+
+impl CachedSettings for DitherSetting {
+    fn set(gl: &Gl, value: &Self) {
+        if value.0 {
+            gl.context().enable(Context::DITHER)
+        } else {
+            gl.context().disable(Context::DITHER)
+        }
+    }
+    fn get_cached(cache: &impl Deref<Target = SettingsCache>) -> Self {
+        cache.dither
+    }
+    fn set_cached(cache: &mut impl DerefMut<Target = SettingsCache>, value: &Self) {
+        cache.dither = *value;
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SampleCoverageSetting {
+    alpha_to_coverage: bool,
+    coverage: Option<(f32, bool)>,
+}
+
+impl CachedSettings for SampleCoverageSetting {
+    fn set(gl: &Gl, value: &Self) {
+        let ref context = gl.context();
+        if value.alpha_to_coverage {
+            context.enable(Context::SAMPLE_ALPHA_TO_COVERAGE)
+        } else {
+            context.disable(Context::SAMPLE_ALPHA_TO_COVERAGE)
+        }
+        match value.coverage {
+            Some((coverage, invert)) => {
+                context.enable(Context::SAMPLE_COVERAGE);
+                context.sample_coverage(coverage, invert);
+            },
+            None => context.disable(Context::SAMPLE_COVERAGE),
+        }
+    }
+    fn get_cached(cache: &impl Deref<Target = SettingsCache>) -> Self {
+        cache.sample_coverage
+    }
+    fn set_cached(cache: &mut impl DerefMut<Target = SettingsCache>, value: &Self) {
+        cache.sample_coverage = *value;
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TextureSetting {
+    index: u32,
+    texture: Option<Texture>,
+}
+
+impl TextureSetting {
+    pub(self) fn set_texture(gl: &Gl, texture: Option<&Texture>) {
+        gl.context().bind_texture(
+            Context::TEXTURE_2D,
+            texture
+                .filter(|texture| !texture.data.disposed.get())
+                .map(|texture| texture.data.handle.clone())
+                .as_ref()
+        );
+    }
+}
+
+impl Settings for TextureSetting {
+    fn apply<R, F: FnOnce() -> R>(&self, gl: &Gl, cache: &RefCell<SettingsCache>, callback: F) -> R {
+        if self.index >= gl.max_texture_units() {
+            console::error_1(&format!(
+                "Texture unit {} is out of range, the context has {} units, the texture is not bound",
+                self.index, gl.max_texture_units()
+            ).into());
+            return callback();
+        }
+        if !self.texture.as_ref().map_or(true, |texture| gl.check_owner(&texture.gl(), "Texture")) {
+            return callback();
+        }
+        // Binding goes to the active unit, so keep the unit selected for the whole scope
+        ActiveTextureSetting(self.index).apply(gl, cache, || {
+            let previous = cache.borrow().textures[self.index as usize].clone();
+            cache.borrow_mut().textures[self.index as usize] = self.texture.as_ref().map(|texture| Rc::downgrade(&texture.data));
+            Self::set_texture(gl, self.texture.as_ref());
+            let result = callback();
+            // The previous texture may have been dropped inside the callback, then there is nothing to restore
+            let previous = previous.and_then(|previous| previous.upgrade()).map(|data| Texture { data: data });
+            Self::set_texture(gl, previous.as_ref());
+            cache.borrow_mut().textures[self.index as usize] = previous.as_ref().map(|texture| Rc::downgrade(&texture.data));
+            result
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TextureArraySetting {
+    index: u32,
+    texture_array: Option<TextureArray>,
+}
+
+impl TextureArraySetting {
+    pub(self) fn set_texture_array(gl: &Gl, texture_array: Option<&TextureArray>) {
+        if let Some(context) = gl.context2() {
+            context.bind_texture(
+                Context2::TEXTURE_2D_ARRAY,
+                texture_array
+                    .map(|texture_array| texture_array.data.handle.clone())
+                    .as_ref()
+            );
+        }
+    }
+}
+
+impl Settings for TextureArraySetting {
+    fn apply<R, F: FnOnce() -> R>(&self, gl: &Gl, cache: &RefCell<SettingsCache>, callback: F) -> R {
+        if self.index >= gl.max_texture_units() {
+            console::error_1(&format!(
+                "Texture unit {} is out of range, the context has {} units, the texture array is not bound",
+                self.index, gl.max_texture_units()
+            ).into());
+            return callback();
+        }
+        if !self.texture_array.as_ref().map_or(true, |texture_array| gl.check_owner(&texture_array.gl(), "Texture array")) {
+            return callback();
+        }
+        ActiveTextureSetting(self.index).apply(gl, cache, || {
+            let index = self.index as usize;
+            let previous = cache.borrow().texture_arrays[index].clone();
+            cache.borrow_mut().texture_arrays[index] = self.texture_array.as_ref().map(|texture_array| Rc::downgrade(&texture_array.data));
+            Self::set_texture_array(gl, self.texture_array.as_ref());
+            let result = callback();
+            let previous = previous.and_then(|previous| previous.upgrade()).map(|data| TextureArray { data: data });
+            Self::set_texture_array(gl, previous.as_ref());
+            cache.borrow_mut().texture_arrays[index] = previous.as_ref().map(|texture_array| Rc::downgrade(&texture_array.data));
+            result
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SamplerSetting {
+    unit: u32,
+    sampler: Option<Sampler>,
+}
+
+impl SamplerSetting {
+    pub(self) fn set_sampler(gl: &Gl, unit: u32, sampler: Option<&Sampler>) {
+        if let Some(context) = gl.context2() {
+            context.bind_sampler(unit, sampler.map(|sampler| sampler.data.handle.clone()).as_ref());
+        }
+    }
+}
+
+impl Settings for SamplerSetting {
+    fn apply<R, F: FnOnce() -> R>(&self, gl: &Gl, cache: &RefCell<SettingsCache>, callback: F) -> R {
+        if self.unit >= gl.max_texture_units() {
+            console::error_1(&format!(
+                "Texture unit {} is out of range, the context has {} units, the sampler is not bound",
+                self.unit, gl.max_texture_units()
+            ).into());
+            return callback();
+        }
+        if !self.sampler.as_ref().map_or(true, |sampler| gl.check_owner(&sampler.gl(), "Sampler")) {
+            return callback();
+        }
+        let previous = cache.borrow().samplers[self.unit as usize].clone();
+        if previous == self.sampler && !gl.explicit_unbind() {
+            return callback();
+        }
+        cache.borrow_mut().samplers[self.unit as usize] = self.sampler.clone();
+        Self::set_sampler(gl, self.unit, self.sampler.as_ref());
+        let result = callback();
+        Self::set_sampler(gl, self.unit, previous.as_ref());
+        cache.borrow_mut().samplers[self.unit as usize] = previous;
+        return result;
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextureFilterSetting {
+    texture: Texture,
+    filter: TextureFilter,
+}
+
+impl Settings for TextureFilterSetting {
+    fn apply<R, F: FnOnce() -> R>(&self, _: &Gl, _: &RefCell<SettingsCache>, callback: F) -> R {
+        let previous = self.texture.filter();
+        let current = self.filter;
+        self.texture.set_filter(current);
+        let result = callback();
+        self.texture.set_filter(previous);
+        return result;
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TextureFiltersSetting(Vec<(Texture, TextureFilter)>);
+
+impl Settings for TextureFiltersSetting {
+    fn apply<R, F: FnOnce() -> R>(&self, _: &Gl, _: &RefCell<SettingsCache>, callback: F) -> R {
+        let changed: Vec<(&Texture, TextureFilter)> = self.0.iter()
+            .filter(|(texture, filter)| texture.filter() != *filter)
+            .map(|(texture, _)| (texture, texture.filter()))
+            .collect();
+        for (texture, filter) in self.0.iter() {
+            texture.set_filter(*filter);
+        }
+        let result = callback();
+        for (texture, previous) in changed.into_iter().rev() {
+            texture.set_filter(previous);
+        }
+        return result;
+    }
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, TryFromPrimitive, IntoPrimitive, PartialEq, Eq)]
+pub enum CompareFunction {
+    Never = Context::NEVER,
+    Less = Context::LESS,
+    Equal = Context::EQUAL,
+    LessOrEqual = Context::LEQUAL,
+    Greater = Context::GREATER,
+    NotEqual = Context::NOTEQUAL,
+    GreaterOrEqual = Context::GEQUAL,
+    Always = Context::ALWAYS,
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, TryFromPrimitive, IntoPrimitive, PartialEq, Eq)]
+pub enum StencilOp {
+    Keep = Context::KEEP,
+    Zero = Context::ZERO,
+    Replace = Context::REPLACE,
+    Increment = Context::INCR,
+    IncrementWrap = Context::INCR_WRAP,
+    Decrement = Context::DECR,
+    DecrementWrap = Context::DECR_WRAP,
+    Invert = Context::INVERT,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StencilTestSetting(bool);
+
+impl CachedSettings for StencilTestSetting {
+    fn set(gl: &Gl, value: &Self) {
+        if value.0 {
+            gl.context().enable(Context::STENCIL_TEST)
+        } else {
+            gl.context().disable(Context::STENCIL_TEST)
+        }
+    }
+    fn get_cached(cache: &impl Deref<Target = SettingsCache>) -> Self {
+        cache.stencil_test
+    }
+    fn set_cached(cache: &mut impl DerefMut<Target = SettingsCache>, value: &Self) {
+        cache.stencil_test = *value;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StencilFuncSetting {
+    func: CompareFunction,
+    reference: i32,
+    mask: u32,
+}
+
+impl Default for StencilFuncSetting {
+    fn default() -> Self {
+        StencilFuncSetting {
+            func: CompareFunction::Always,
+            reference: 0,
+            mask: !0,
+        }
+    }
+}
+
+impl CachedSettings for StencilFuncSetting {
+    fn set(gl: &Gl, value: &Self) {
+        gl.context().stencil_func(value.func.into(), value.reference, value.mask);
+    }
+    fn get_cached(cache: &impl Deref<Target = SettingsCache>) -> Self {
+        cache.stencil_func
+    }
+    fn set_cached(cache: &mut impl DerefMut<Target = SettingsCache>, value: &Self) {
+        cache.stencil_func = *value;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StencilOpSetting {
+    fail: StencilOp,
+    z_fail: StencilOp,
+    z_pass: StencilOp,
+}
+
+impl Default for StencilOpSetting {
+    fn default() -> Self {
+        StencilOpSetting {
+            fail: StencilOp::Keep,
+            z_fail: StencilOp::Keep,
+            z_pass: StencilOp::Keep,
+        }
+    }
+}
+
+impl CachedSettings for StencilOpSetting {
+    fn set(gl: &Gl, value: &Self) {
+        gl.context().stencil_op(value.fail.into(), value.z_fail.into(), value.z_pass.into());
+    }
+    fn get_cached(cache: &impl Deref<Target = SettingsCache>) -> Self {
+        cache.stencil_op
+    }
+    fn set_cached(cache: &mut impl DerefMut<Target = SettingsCache>, value: &Self) {
+        cache.stencil_op = *value;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StencilMaskSetting(u32);
+
+impl Default for StencilMaskSetting {
+    fn default() -> Self {
+        StencilMaskSetting(!0)
+    }
+}
+
+impl CachedSettings for StencilMaskSetting {
+    fn set(gl: &Gl, value: &Self) {
+        gl.context().stencil_mask(value.0);
+    }
+    fn get_cached(cache: &impl Deref<Target = SettingsCache>) -> Self {
+        cache.stencil_mask
+    }
+    fn set_cached(cache: &mut impl DerefMut<Target = SettingsCache>, value: &Self) {
+        cache.stencil_mask = *value;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColorMaskSetting([bool; 4]);
+
+impl Default for ColorMaskSetting {
+    fn default() -> Self {
+        ColorMaskSetting([true; 4])
+    }
+}
+
+impl CachedSettings for ColorMaskSetting {
+    fn set(gl: &Gl, value: &Self) {
+        let [red, green, blue, alpha] = value.0;
+        gl.context().color_mask(red, green, blue, alpha);
+    }
+    fn get_cached(cache: &impl Deref<Target = SettingsCache>) -> Self {
+        cache.color_mask
+    }
+    fn set_cached(cache: &mut impl DerefMut<Target = SettingsCache>, value: &Self) {
+        cache.color_mask = *value;
+    }
+}
+
+/**
+ * Scissor test, `None` disables it.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ScissorSetting(Option<(i32, i32, i32, i32)>);
+
+impl CachedSettings for ScissorSetting {
+    fn set(gl: &Gl, value: &Self) {
+        match value.0 {
+            Some((x, y, width, height)) => {
+                gl.context().enable(Context::SCISSOR_TEST);
+                gl.context().scissor(x, y, width, height);
+            },
+            None => gl.context().disable(Context::SCISSOR_TEST),
+        }
+    }
+    fn get_cached(cache: &impl Deref<Target = SettingsCache>) -> Self {
+        cache.scissor
+    }
+    fn set_cached(cache: &mut impl DerefMut<Target = SettingsCache>, value: &Self) {
+        cache.scissor = *value;
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ViewportSetting(pub(super) i32, pub(super) i32, pub(super) i32, pub(super) i32);
+
+impl CachedSettings for ViewportSetting {
+    fn set(gl: &Gl, value: &Self) {
+        let ViewportSetting(x, y, width, height) = *value;
+        let (max_width, max_height) = gl.max_viewport_dims();
+        if width as u32 > max_width || height as u32 > max_height {
+            console::warn_1(&format!(
+                "Viewport {}x{} exceeds MAX_VIEWPORT_DIMS {}x{}, GL clamps it",
+                width, height, max_width, max_height
+            ).into());
+        }
+        gl.context().viewport(x, y, width, height);
+    }
+    fn get_cached(cache: &impl Deref<Target = SettingsCache>) -> Self {
+        cache.viewport
+    }
+    fn set_cached(cache: &mut impl DerefMut<Target = SettingsCache>, value: &Self) {
+        cache.viewport = *value;
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ClearColorSetting(Color);
+
+impl CachedSettings for ClearColorSetting {
+    fn set(gl: &Gl, value: &Self) {
+        let Color { red, green, blue, alpha } = value.0;
+        gl.context().clear_color(red, green, blue, alpha);
+    }
+    fn get_cached(cache: &impl Deref<Target = SettingsCache>) -> Self {
+        cache.clear_color
+    }
+    fn set_cached(cache: &mut impl DerefMut<Target = SettingsCache>, value: &Self) {
+        cache.clear_color = *value;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColorspaceConversionSetting(pub(super) bool);
+
+impl Default for ColorspaceConversionSetting {
+    fn default() -> Self {
+        ColorspaceConversionSetting(true)
+    }
+}
+
+impl CachedSettings for ColorspaceConversionSetting {
+    fn set(gl: &Gl, value: &Self) {
+        let conversion = if value.0 { Context::BROWSER_DEFAULT_WEBGL } else { Context::NONE };
+        gl.context().pixel_storei(Context::UNPACK_COLORSPACE_CONVERSION_WEBGL, conversion as i32);
+    }
+    fn get_cached(cache: &impl Deref<Target = SettingsCache>) -> Self {
+        cache.colorspace_conversion
+    }
+    fn set_cached(cache: &mut impl DerefMut<Target = SettingsCache>, value: &Self) {
+        cache.colorspace_conversion = *value;
+    }
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, TryFromPrimitive, IntoPrimitive, PartialEq, Eq)]
+pub enum Quality {
+    Fastest = Context::FASTEST,
+    Nicest = Context::NICEST,
+    DontCare = Context::DONT_CARE,
+}
+
+impl Default for Quality {
+    fn default() -> Self {
+        Quality::DontCare
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MipmapHintSetting(pub(super) Quality);
+
+impl CachedSettings for MipmapHintSetting {
+    fn set(gl: &Gl, value: &Self) {
+        gl.context().hint(Context::GENERATE_MIPMAP_HINT, value.0.into());
+    }
+    fn get_cached(cache: &impl Deref<Target = SettingsCache>) -> Self {
+        cache.mipmap_hint
+    }
+    fn set_cached(cache: &mut impl DerefMut<Target = SettingsCache>, value: &Self) {
+        cache.mipmap_hint = *value;
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UnpackRegionSetting {
+    row_length: u32,
+    skip_pixels: u32,
+    skip_rows: u32,
+}
+
+impl UnpackRegionSetting {
+    /**
+     * Pixels an upload of the size spans in the source with the region of the current scope,
+     * `None` if the source is tightly packed.
+     */
+    pub(super) fn applied_span(gl: &Gl, width: u32, height: u32) -> Option<usize> {
+        let region = Self::get_cached(&gl.settings_cache().borrow());
+        if region == UnpackRegionSetting::default() || !gl.is_webgl2() {
+            return None;
+        }
+        if width == 0 || height == 0 {
+            return Some(0);
+        }
+        let row_length = if region.row_length == 0 { width } else { region.row_length } as usize;
+        return Some(
+            (region.skip_rows as usize + height as usize - 1) * row_length + region.skip_pixels as usize + width as usize
+        );
+    }
+}
+
+impl CachedSettings for UnpackRegionSetting {
+    fn set(gl: &Gl, value: &Self) {
+        match gl.context2() {
+            Some(context) => {
+                context.pixel_storei(Context2::UNPACK_ROW_LENGTH, value.row_length as i32);
+                context.pixel_storei(Context2::UNPACK_SKIP_PIXELS, value.skip_pixels as i32);
+                context.pixel_storei(Context2::UNPACK_SKIP_ROWS, value.skip_rows as i32);
+            },
+            None if *value != UnpackRegionSetting::default() => {
+                console::error_1(&"Unpack region requires WebGL 2, uploads read tightly packed data".into());
+            },
+            None => {},
+        }
+    }
+    fn get_cached(cache: &impl Deref<Target = SettingsCache>) -> Self {
+        cache.unpack_region
+    }
+    fn set_cached(cache: &mut impl DerefMut<Target = SettingsCache>, value: &Self) {
+        cache.unpack_region = *value;
+    }
+}
+
+/**
+ * Regression tests for the cache eviction of dropped resources, run with
+ * `wasm-pack test --headless --firefox -- --features testing`.
+ */
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_test::*;
+    use web_sys::HtmlCanvasElement;
+    use web_sys::WebGlRenderingContext as Context;
+
+    use super::*;
+    use super::super::data_buffer::BufferUsage;
+    use super::super::texture::TextureType;
+    use super::super::texture::TextureFormat;
+    use super::super::texture::TextureContent;
+    use super::super::testing;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn create_gl() -> Gl {
+        let canvas = web_sys::window().unwrap()
+            .document().unwrap()
+            .create_element("canvas").unwrap()
+            .dyn_into::<HtmlCanvasElement>().unwrap();
+        return Gl::new(&canvas).unwrap();
+    }
+
+    /**
+     * Bind outside of a scope, so the cache is the only place the binding is recorded.
+     */
+    fn bind_array_buffer(gl: &Gl, buffer: &ArrayBuffer) {
+        let value = ArrayBufferSetting(Some(buffer.clone()));
+        ArrayBufferSetting::set(gl, &value);
+        ArrayBufferSetting::set_cached(&mut gl.settings_cache().borrow_mut(), &value);
+    }
+
+    #[wasm_bindgen_test]
+    fn dropped_array_buffer_is_unbound() {
+        let gl = create_gl();
+        let dropped = ArrayBuffer::new(gl.clone(), &[0.0f32, 1.0, 2.0], BufferUsage::Static);
+        let other = ArrayBuffer::new(gl.clone(), &[0.0f32; 3], BufferUsage::Static);
+        bind_array_buffer(&gl, &dropped);
+        testing::assert_array_buffer(&gl, Some(&dropped));
+
+        drop(dropped);
+        testing::assert_array_buffer(&gl, None);
+        assert_eq!(ArrayBufferSetting::get_cached(&gl.settings_cache().borrow()), ArrayBufferSetting(None));
+
+        other.write(&[3.0f32, 4.0, 5.0], BufferUsage::Static).unwrap();
+        assert_eq!(gl.context().get_error(), Context::NO_ERROR);
+        testing::assert_array_buffer(&gl, None);
+    }
+
+    #[wasm_bindgen_test]
+    fn dropped_texture_is_evicted() {
+        let gl = create_gl();
+        let dropped = Texture::new(gl.clone(), 1, 1, TextureType::Byte, TextureFormat::Rgba, TextureContent::None);
+        TextureSetting::set_texture(&gl, Some(&dropped));
+        gl.settings_cache().borrow_mut().textures[0] = Some(Rc::downgrade(&dropped.data));
+        testing::assert_bound_texture(&gl, 0, Some(&dropped));
+
+        drop(dropped);
+        assert!(gl.settings_cache().borrow().textures[0].is_none());
+        testing::assert_bound_texture(&gl, 0, None);
+
+        let other = Texture::new(gl.clone(), 1, 1, TextureType::Byte, TextureFormat::Rgba, TextureContent::None);
+        other.write_sub_image(0, 0, 1, 1, &[255, 0, 0, 255]).unwrap();
+        assert_eq!(gl.context().get_error(), Context::NO_ERROR);
+        testing::assert_bound_texture(&gl, 0, None);
+    }
+
+    #[wasm_bindgen_test]
+    fn dropped_framebuffer_restores_canvas() {
+        let gl = create_gl();
+        let color = Texture::new(gl.clone(), 1, 1, TextureType::Byte, TextureFormat::Rgba, TextureContent::None);
+        let dropped = Framebuffer::new(gl.clone(), color).unwrap();
+        FramebufferSetting::set_framebuffer(&gl, Some(&dropped));
+        gl.settings_cache().borrow_mut().framebuffer = Some(Rc::downgrade(&dropped.data));
+        testing::assert_framebuffer(&gl, Some(&dropped));
+
+        drop(dropped);
+        testing::assert_framebuffer(&gl, None);
+
+        gl.clear(Color::from([1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(gl.context().get_error(), Context::NO_ERROR);
+        let pixel = gl.read_image_data(0, 0, 1, 1).unwrap().data();
+        assert_eq!(pixel.0, vec![255, 0, 0, 255]);
+    }
+}
@@ -0,0 +1,109 @@
+use super::gl::Gl;
+use super::settings::Settings;
+use super::settings::AttributeSetting;
+use super::attribute::AttributeLayout;
+use super::data_buffer::ArrayBuffer;
+use super::data_buffer::BufferUsage;
+use super::primitive::PrimitiveType;
+use super::program::Program;
+use super::texture::Texture;
+use super::pod::Pod;
+
+/**
+ * Per instance data of a sprite. Position and size are in the units the program expects,
+ * `uv` is `[u, v, width, height]` of the region in the texture.
+ */
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Sprite {
+    pub position: [f32; 2],
+    pub size: [f32; 2],
+    pub uv: [f32; 4],
+    pub color: [f32; 4],
+}
+
+// Only f32 fields, so there is no padding
+unsafe impl Pod for Sprite {}
+
+/**
+ * Collects sprites and draws them with one instanced call per flush.
+ *
+ * The program reads the unit quad corner from `a_corner` (vec2 in 0..1) and the per instance
+ * `a_position`, `a_size`, `a_uv` and `a_color` attributes, those it doesn't use are skipped.
+ * The texture is bound to unit 0.
+ */
+#[derive(Debug)]
+pub struct SpriteBatch {
+    pub(self) gl: Gl,
+    pub(self) quad: ArrayBuffer,
+    pub(self) instances: ArrayBuffer,
+    pub(self) sprites: Vec<Sprite>,
+}
+
+impl SpriteBatch {
+    pub fn new(gl: Gl) -> SpriteBatch {
+        let corners: [f32; 8] = [0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0];
+        let no_sprites: [Sprite; 0] = [];
+        SpriteBatch {
+            quad: ArrayBuffer::new(gl.clone(), &corners, BufferUsage::Static),
+            instances: ArrayBuffer::new(gl.clone(), &no_sprites, BufferUsage::Stream),
+            gl: gl,
+            sprites: Vec::new(),
+        }
+    }
+
+    pub fn gl(&self) -> Gl {
+        self.gl.clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.sprites.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sprites.is_empty()
+    }
+
+    pub fn draw(&mut self, sprite: Sprite) {
+        self.sprites.push(sprite);
+    }
+
+    /**
+     * Upload the collected sprites, draw them with the program and texture and start a new batch.
+     */
+    pub fn flush(&mut self, program: &Program, texture: &Texture) {
+        if self.sprites.is_empty() {
+            return;
+        }
+        self.instances.write(&self.sprites, BufferUsage::Stream).unwrap();
+
+        let stride = std::mem::size_of::<Sprite>() as u32;
+        let ref instances = self.instances;
+        let instance_attribute = |name: &str, size: u32, offset: u32| {
+            program.attribute_location(name).map(|location| AttributeSetting::new(
+                location,
+                instances.clone(),
+                AttributeLayout::floats(size).stride(stride).offset(offset).divisor(1),
+            ))
+        };
+        let corner = program.attribute_location("a_corner").map(|location| {
+            AttributeSetting::new(location, self.quad.clone(), AttributeLayout::floats(2))
+        });
+
+        let settings = Gl::settings()
+            .program(program.clone())
+            .texture(0, texture.clone())
+            .maybe(corner)
+            .maybe(instance_attribute("a_position", 2, 0))
+            .maybe(instance_attribute("a_size", 2, 8))
+            .maybe(instance_attribute("a_uv", 4, 16))
+            .maybe(instance_attribute("a_color", 4, 32));
+
+        let count = self.sprites.len() as u32;
+        let ref gl = self.gl;
+        gl.apply(settings, || {
+            gl.draw_arrays_instanced(PrimitiveType::TriangleStrip, 0, 4, count);
+        });
+        self.sprites.clear();
+    }
+}
@@ -0,0 +1,117 @@
+/*!
+ * Assertions against the real GL state, bypassing the settings cache.
+ * Meant for regression tests of the caching and restore logic, available with the `testing` feature.
+ */
+use wasm_bindgen::JsCast;
+use web_sys::{
+    WebGlBuffer,
+    WebGlFramebuffer,
+    WebGlProgram,
+    WebGlTexture,
+    WebGlRenderingContext as Context,
+};
+
+use super::gl::Gl;
+use super::texture::Texture;
+use super::program::Program;
+use super::data_buffer::ArrayBuffer;
+use super::data_buffer::ElementBuffer;
+use super::framebuffer::Framebuffer;
+use super::color::Color;
+
+pub(self) fn parameter<T: JsCast>(gl: &Gl, name: u32) -> Option<T> {
+    gl.context().get_parameter(name)
+        .ok()
+        .and_then(|value| value.dyn_into().ok())
+}
+
+pub(self) fn assert_enabled(gl: &Gl, capability: u32, name: &str, expected: bool) {
+    let actual = gl.context().is_enabled(capability);
+    assert_eq!(actual, expected, "{} is {}, expected {}", name, enabled_str(actual), enabled_str(expected));
+}
+
+pub(self) fn enabled_str(value: bool) -> &'static str {
+    if value { "enabled" } else { "disabled" }
+}
+
+pub fn assert_blend_enabled(gl: &Gl, expected: bool) {
+    assert_enabled(gl, Context::BLEND, "BLEND", expected);
+}
+
+pub fn assert_depth_test_enabled(gl: &Gl, expected: bool) {
+    assert_enabled(gl, Context::DEPTH_TEST, "DEPTH_TEST", expected);
+}
+
+pub fn assert_stencil_test_enabled(gl: &Gl, expected: bool) {
+    assert_enabled(gl, Context::STENCIL_TEST, "STENCIL_TEST", expected);
+}
+
+pub fn assert_scissor_test_enabled(gl: &Gl, expected: bool) {
+    assert_enabled(gl, Context::SCISSOR_TEST, "SCISSOR_TEST", expected);
+}
+
+/**
+ * Active texture unit, 0-based like `Gl::set_active_texture`.
+ */
+pub fn assert_active_texture(gl: &Gl, expected: u32) {
+    let actual = gl.context().get_parameter(Context::ACTIVE_TEXTURE)
+        .ok()
+        .and_then(|value| value.as_f64())
+        .map(|value| value as u32 - Context::TEXTURE0);
+    assert_eq!(actual, Some(expected), "active texture unit mismatch");
+}
+
+/**
+ * Texture bound to `TEXTURE_2D` on the unit. The active unit is switched to read it and restored afterwards.
+ */
+pub fn assert_bound_texture(gl: &Gl, unit: u32, expected: Option<&Texture>) {
+    let ref context = gl.context();
+    let active = context.get_parameter(Context::ACTIVE_TEXTURE)
+        .ok()
+        .and_then(|value| value.as_f64())
+        .map_or(Context::TEXTURE0, |value| value as u32);
+    context.active_texture(Context::TEXTURE0 + unit);
+    let actual: Option<WebGlTexture> = parameter(gl, Context::TEXTURE_BINDING_2D);
+    context.active_texture(active);
+    assert_eq!(
+        actual.as_ref(),
+        expected.map(|texture| &texture.data.handle),
+        "texture bound to unit {} mismatch", unit,
+    );
+}
+
+pub fn assert_program(gl: &Gl, expected: Option<&Program>) {
+    let actual: Option<WebGlProgram> = parameter(gl, Context::CURRENT_PROGRAM);
+    assert_eq!(actual, expected.map(|program| program.handle()), "current program mismatch");
+}
+
+pub fn assert_array_buffer(gl: &Gl, expected: Option<&ArrayBuffer>) {
+    let actual: Option<WebGlBuffer> = parameter(gl, Context::ARRAY_BUFFER_BINDING);
+    assert_eq!(actual, expected.map(|buffer| buffer.handle()), "array buffer binding mismatch");
+}
+
+pub fn assert_element_buffer(gl: &Gl, expected: Option<&ElementBuffer>) {
+    let actual: Option<WebGlBuffer> = parameter(gl, Context::ELEMENT_ARRAY_BUFFER_BINDING);
+    assert_eq!(actual, expected.map(|buffer| buffer.handle()), "element buffer binding mismatch");
+}
+
+/**
+ * Bound framebuffer, `None` is the canvas.
+ */
+pub fn assert_framebuffer(gl: &Gl, expected: Option<&Framebuffer>) {
+    let actual: Option<WebGlFramebuffer> = parameter(gl, Context::FRAMEBUFFER_BINDING);
+    assert_eq!(
+        actual.as_ref(),
+        expected.map(|framebuffer| &framebuffer.data.handle),
+        "framebuffer binding mismatch",
+    );
+}
+
+pub fn assert_clear_color<C: Into<Color>>(gl: &Gl, expected: C) {
+    let actual: Option<js_sys::Float32Array> = parameter(gl, Context::COLOR_CLEAR_VALUE);
+    let mut values = [0.0; 4];
+    if let Some(actual) = actual {
+        actual.copy_to(&mut values);
+    }
+    assert_eq!(Color::from(values), expected.into(), "clear color mismatch");
+}
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use super::gl::Gl;
+use super::settings::Settings;
+use super::settings::BlendFactor;
+use super::sprite_batch::Sprite;
+use super::sprite_batch::SpriteBatch;
+use super::program::Program;
+use super::texture::Texture;
+use super::color::Color;
+
+/**
+ * Metrics of a glyph in a font atlas. `uv` is `[u, v, width, height]` of its region in the atlas,
+ * `offset` is from the pen position to the top left corner of the glyph quad.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Glyph {
+    pub uv: [f32; 4],
+    pub size: [f32; 2],
+    pub offset: [f32; 2],
+    pub advance: f32,
+}
+
+/**
+ * Draws strings from a pre-baked font atlas, one instanced draw call per string through `SpriteBatch`,
+ * so the program has the same inputs as for sprites. Coordinates have y pointing down like the canvas,
+ * lines start at the `position` x and continue `line_height` lower. Characters missing from the glyph
+ * map are skipped.
+ */
+#[derive(Debug)]
+pub struct TextRenderer {
+    pub(self) batch: SpriteBatch,
+    pub(self) atlas: Texture,
+    pub(self) glyphs: HashMap<char, Glyph>,
+    pub(self) line_height: f32,
+}
+
+impl TextRenderer {
+    pub fn new(gl: Gl, atlas: Texture, glyphs: HashMap<char, Glyph>, line_height: f32) -> TextRenderer {
+        TextRenderer {
+            batch: SpriteBatch::new(gl),
+            atlas: atlas,
+            glyphs: glyphs,
+            line_height: line_height,
+        }
+    }
+
+    /**
+     * Monospaced font laid out in the atlas as a grid of `columns` cells per row, in character order
+     * from `first` (usually `' '`), `count` characters. Glyphs are drawn `cell_size` large.
+     */
+    pub fn monospaced(gl: Gl, atlas: Texture, columns: u32, first: char, count: u32, cell_size: [f32; 2]) -> TextRenderer {
+        let columns = columns.max(1);
+        let rows = (count + columns - 1) / columns;
+        let (cell_u, cell_v) = (1.0 / columns as f32, 1.0 / rows.max(1) as f32);
+        let glyphs = (0..count)
+            .filter_map(|index| std::char::from_u32(first as u32 + index).map(|character| (index, character)))
+            .map(|(index, character)| {
+                let (column, row) = (index % columns, index / columns);
+                (character, Glyph {
+                    uv: [column as f32 * cell_u, row as f32 * cell_v, cell_u, cell_v],
+                    size: cell_size,
+                    offset: [0.0, 0.0],
+                    advance: cell_size[0],
+                })
+            })
+            .collect();
+        TextRenderer::new(gl, atlas, glyphs, cell_size[1])
+    }
+
+    pub fn atlas(&self) -> Texture {
+        self.atlas.clone()
+    }
+
+    pub fn line_height(&self) -> f32 {
+        self.line_height
+    }
+
+    /**
+     * Width and height of the text block, for alignment.
+     */
+    pub fn measure(&self, text: &str) -> (f32, f32) {
+        let mut width: f32 = 0.0;
+        let mut lines = 0;
+        for line in text.split('\n') {
+            let line_width: f32 = line.chars()
+                .filter_map(|character| self.glyphs.get(&character))
+                .map(|glyph| glyph.advance)
+                .sum();
+            width = width.max(line_width);
+            lines += 1;
+        }
+        return (width, lines as f32 * self.line_height);
+    }
+
+    /**
+     * Draw the text with its top left corner at `position`, blended over the target with straight alpha.
+     */
+    pub fn draw<C: Into<Color>>(&mut self, program: &Program, text: &str, position: [f32; 2], color: C) {
+        let color = color.into().to_f32_array();
+        let mut pen = position;
+        for character in text.chars() {
+            if character == '\n' {
+                pen = [position[0], pen[1] + self.line_height];
+                continue;
+            }
+            if let Some(glyph) = self.glyphs.get(&character) {
+                self.batch.draw(Sprite {
+                    position: [pen[0] + glyph.offset[0], pen[1] + glyph.offset[1]],
+                    size: glyph.size,
+                    uv: glyph.uv,
+                    color: color,
+                });
+                pen[0] += glyph.advance;
+            }
+        }
+
+        let gl = self.batch.gl();
+        let ref atlas = self.atlas;
+        let ref mut batch = self.batch;
+        gl.apply(
+            Gl::settings()
+                .blend(true)
+                .blend_func(BlendFactor::SrcAlpha, BlendFactor::OneMinusSrcAlpha),
+            || batch.flush(program, atlas)
+        );
+    }
+}
@@ -1,12 +1,26 @@
 use std::rc::Rc;
 use std::cell::Cell;
+use std::cell::RefCell;
 use super::gl::{ Gl };
 use super::settings::{ Settings };
+use super::settings::UnpackRegionSetting;
+use super::error::GlError;
+use super::color::Color;
+use super::pod::Pod;
+use super::pod::bytes_of;
+use js_sys::Promise;
+use js_sys::Object;
+use js_sys::Uint8Array;
+use js_sys::Float32Array;
+use std::convert::TryFrom;
+use wasm_bindgen_futures::JsFuture;
 
 use web_sys::{
+    console,
     HtmlImageElement,
     WebGlTexture,
     WebGlRenderingContext as Context,
+    WebGl2RenderingContext as Context2,
 };
 use num_enum::{
     TryFromPrimitive,
@@ -26,6 +40,76 @@ impl Default for TextureFilter {
     }
 }
 
+/**
+ * Minification filter, unlike `TextureFilter` it can sample between mip levels.
+ */
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, TryFromPrimitive, IntoPrimitive, PartialEq, Eq)]
+pub enum MinFilter {
+    Nearest = Context::NEAREST as i32,
+    Linear = Context::LINEAR as i32,
+    NearestMipmapNearest = Context::NEAREST_MIPMAP_NEAREST as i32,
+    LinearMipmapNearest = Context::LINEAR_MIPMAP_NEAREST as i32,
+    NearestMipmapLinear = Context::NEAREST_MIPMAP_LINEAR as i32,
+    LinearMipmapLinear = Context::LINEAR_MIPMAP_LINEAR as i32,
+}
+
+impl MinFilter {
+    pub fn is_mipmap(&self) -> bool {
+        match self {
+            MinFilter::Nearest | MinFilter::Linear => false,
+            _ => true,
+        }
+    }
+
+    /**
+     * The filter without sampling between mip levels.
+     */
+    pub fn without_mipmap(&self) -> MinFilter {
+        match self {
+            MinFilter::Nearest | MinFilter::NearestMipmapNearest | MinFilter::NearestMipmapLinear => MinFilter::Nearest,
+            MinFilter::Linear | MinFilter::LinearMipmapNearest | MinFilter::LinearMipmapLinear => MinFilter::Linear,
+        }
+    }
+
+    /**
+     * The filter without linear interpolation, within and between levels.
+     */
+    pub fn without_linear(&self) -> MinFilter {
+        match self {
+            MinFilter::Nearest | MinFilter::Linear => MinFilter::Nearest,
+            _ => MinFilter::NearestMipmapNearest,
+        }
+    }
+
+    pub fn is_linear(&self) -> bool {
+        self.without_linear() != *self
+    }
+}
+
+impl From<TextureFilter> for MinFilter {
+    fn from(filter: TextureFilter) -> Self {
+        match filter {
+            TextureFilter::Nearest => MinFilter::Nearest,
+            TextureFilter::Linear => MinFilter::Linear,
+        }
+    }
+}
+
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, TryFromPrimitive, IntoPrimitive, PartialEq, Eq)]
+pub enum TextureWrap {
+    Repeat = Context::REPEAT as i32,
+    ClampToEdge = Context::CLAMP_TO_EDGE as i32,
+    MirroredRepeat = Context::MIRRORED_REPEAT as i32,
+}
+
+impl Default for TextureWrap {
+    fn default() -> Self {
+        TextureWrap::Repeat
+    }
+}
+
 #[repr(u32)]
 #[derive(Clone, Copy, Debug, TryFromPrimitive, IntoPrimitive, PartialEq, Eq)]
 pub enum TextureType {
@@ -41,6 +125,70 @@ pub enum TextureFormat {
     LuminanceAlpha = Context::LUMINANCE_ALPHA,
     Rgb = Context::RGB,
     Rgba = Context::RGBA,
+    /**
+     * Single channel format, WebGL 2 only and requires a sized storage, see `Texture::new_immutable`.
+     */
+    Red = Context2::RED,
+}
+
+impl TextureType {
+    pub fn bytes_per_channel(&self) -> u32 {
+        match self {
+            TextureType::Byte => 1,
+            TextureType::Float => 4,
+        }
+    }
+
+    /**
+     * JS typed array matching the type, with `len` channel values. Read and upload paths
+     * must use it instead of picking the array kind themselves, e.g. floats need a `Float32Array`.
+     */
+    pub fn typed_array_for(&self, len: u32) -> Object {
+        match self {
+            TextureType::Byte => Uint8Array::new_with_length(len).into(),
+            TextureType::Float => Float32Array::new_with_length(len).into(),
+        }
+    }
+
+    /**
+     * Typed array with a copy of the raw texel bytes, GL rejects a `Uint8Array` for float textures.
+     * Trailing bytes that don't make a whole channel are dropped.
+     */
+    pub(super) fn view_of(&self, bytes: &[u8]) -> Object {
+        match self {
+            TextureType::Byte => Uint8Array::from(bytes).into(),
+            TextureType::Float => {
+                let len = bytes.len() / 4 * 4;
+                Float32Array::new(&Uint8Array::from(&bytes[..len]).buffer()).into()
+            },
+        }
+    }
+}
+
+impl TextureFormat {
+    pub fn channels(&self) -> u32 {
+        match self {
+            TextureFormat::Alpha | TextureFormat::Luminance | TextureFormat::Red => 1,
+            TextureFormat::LuminanceAlpha => 2,
+            TextureFormat::Rgb => 3,
+            TextureFormat::Rgba => 4,
+        }
+    }
+
+    /**
+     * Sized internal format for `tex_storage_2d`, `None` for formats WebGL 2 has no sized variant of.
+     */
+    pub fn sized_internal_format(&self, data_type: TextureType) -> Option<u32> {
+        match (self, data_type) {
+            (TextureFormat::Red, TextureType::Byte) => Some(Context2::R8),
+            (TextureFormat::Red, TextureType::Float) => Some(Context2::R32F),
+            (TextureFormat::Rgb, TextureType::Byte) => Some(Context2::RGB8),
+            (TextureFormat::Rgba, TextureType::Byte) => Some(Context2::RGBA8),
+            (TextureFormat::Rgb, TextureType::Float) => Some(Context2::RGB32F),
+            (TextureFormat::Rgba, TextureType::Float) => Some(Context2::RGBA32F),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -50,17 +198,69 @@ pub enum TextureContent {
     Bytes(Vec<u8>),
 }
 
-pub const TEXTURES_COUNT: u32 = 16;
+impl TextureContent {
+    /**
+     * Raw bytes of typed texels, e.g. `f32` channels for `TextureType::Float`.
+     */
+    pub fn from_data<T: Pod>(data: &[T]) -> TextureContent {
+        TextureContent::Bytes(bytes_of(data).to_vec())
+    }
+
+    /**
+     * Multiply RGB of straight alpha `Rgba` bytes by alpha. `UNPACK_PREMULTIPLY_ALPHA_WEBGL` applies to images
+     * only, raw bytes need this instead. Other formats, types and content are returned unchanged.
+     */
+    pub fn premultiply_alpha(self, format: TextureFormat, data_type: TextureType) -> TextureContent {
+        match self {
+            TextureContent::Bytes(mut bytes) if format == TextureFormat::Rgba && data_type == TextureType::Byte => {
+                for pixel in bytes.chunks_exact_mut(4) {
+                    let alpha = pixel[3] as u32;
+                    for channel in pixel[..3].iter_mut() {
+                        *channel = ((*channel as u32 * alpha + 127) / 255) as u8;
+                    }
+                }
+                TextureContent::Bytes(bytes)
+            },
+            content => content,
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct TextureInfo {
     pub(self) gl: Gl,
+    pub(self) id: u64,
     pub(super) handle: WebGlTexture,
     pub(self) width: u32,
     pub(self) height: u32,
     pub(self) data_type: TextureType,
     pub(self) format: TextureFormat,
     pub(self) filter: Cell<TextureFilter>,
+    pub(self) min_filter: Cell<MinFilter>,
+    /**
+     * Set once `generate_mipmap` or `write_mip_level` filled the mip chain, mipmap min filters need it.
+     */
+    pub(self) mipmapped: Cell<bool>,
+    /**
+     * Bit per level uploaded with `write_mip_level`, the base level is always defined.
+     */
+    pub(self) written_levels: Cell<u32>,
+    pub(self) wrap: Cell<TextureWrap>,
+    /**
+     * `TEXTURE_BASE_LEVEL` and `TEXTURE_MAX_LEVEL`, only settable on WebGL 2.
+     */
+    pub(self) mip_range: Cell<(u32, u32)>,
+    pub(self) lod_range: Cell<(f32, f32)>,
+    pub(super) disposed: Cell<bool>,
+    /**
+     * Textures adopted with `from_raw` are owned elsewhere and never deleted by the crate.
+     */
+    pub(self) owned: bool,
+    /**
+     * Number of levels allocated with `tex_storage_2d`, `None` for mutable textures.
+     */
+    pub(self) storage_levels: Option<u32>,
+    pub(self) label: RefCell<Option<String>>,
 }
 
 impl PartialEq<TextureInfo> for TextureInfo {
@@ -73,7 +273,13 @@ impl Eq for TextureInfo {}
 
 impl Drop for TextureInfo {
     fn drop(&mut self) {
-        self.gl.context().delete_texture(Some(&self.handle))
+        if self.owned && !self.disposed.get() {
+            self.gl.context().delete_texture(Some(&self.handle))
+        }
+        self.gl.track_resources(|counts| counts.textures = counts.textures.saturating_sub(1));
+        if let Ok(mut cache) = self.gl.settings_cache().try_borrow_mut() {
+            cache.evict_dropped_textures();
+        }
     }
 }
 
@@ -85,16 +291,46 @@ pub struct Texture {
 impl Texture {
 
     pub fn new(gl: Gl, width: u32, height: u32, data_type: TextureType, format: TextureFormat, data: TextureContent) -> Texture {
+        Texture::new_with_flip_y(gl, width, height, data_type, format, data, false)
+    }
+
+    /**
+     * Like `new`, with `flip_y` the content is uploaded bottom row first, so images come out right side up
+     * with texture coordinates starting at the bottom. The flip applies to this upload only.
+     */
+    pub fn new_with_flip_y(
+        gl: Gl,
+        width: u32,
+        height: u32,
+        data_type: TextureType,
+        format: TextureFormat,
+        data: TextureContent,
+        flip_y: bool,
+    ) -> Texture {
         let handle = gl.context().create_texture().unwrap();
+        let wrap = Texture::default_wrap(width, height);
+        let filter = TextureFilter::default();
+        gl.track_resources(|counts| counts.textures += 1);
         let result = Texture {
             data: Rc::new(TextureInfo {
                 gl: gl.clone(),
+                id: gl.next_id(),
                 handle: handle.clone(),
                 width: width,
                 height: height,
                 data_type: data_type,
                 format: format,
-                filter: Default::default()
+                filter: Cell::new(filter),
+                min_filter: Cell::new(MinFilter::from(filter)),
+                mipmapped: Cell::new(false),
+                written_levels: Cell::new(1),
+                wrap: Cell::new(wrap),
+                mip_range: Cell::new((0, 1000)),
+                lod_range: Cell::new((-1000.0, 1000.0)),
+                disposed: Cell::new(false),
+                owned: true,
+                storage_levels: None,
+                label: RefCell::new(None),
             }),
         };
 
@@ -103,10 +339,10 @@ impl Texture {
         gl.apply(
             Gl::settings().texture(0, result.clone()),
             || {
-                match data {
+                Texture::with_flip_y(&gl, flip_y, || match data {
                     TextureContent::None => {
                         gl.context().tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
-                            Context::TEXTURE0,
+                            Context::TEXTURE_2D,
                             0,
                             format as i32,
                             width as i32,
@@ -119,7 +355,7 @@ impl Texture {
                     },
                     TextureContent::Image(image) => {
                         gl.context().tex_image_2d_with_u32_and_u32_and_image(
-                            Context::TEXTURE0,
+                            Context::TEXTURE_2D,
                             0,
                             format as i32,
                             format,
@@ -128,8 +364,8 @@ impl Texture {
                         ).unwrap();
                     },
                     TextureContent::Bytes(bytes) => {
-                        gl.context().tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
-                            Context::TEXTURE0,
+                        gl.context().tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_array_buffer_view(
+                            Context::TEXTURE_2D,
                             0,
                             format as i32,
                             width as i32,
@@ -137,16 +373,260 @@ impl Texture {
                             0,
                             format,
                             data_type.into(),
-                            Some(&bytes),
+                            Some(&data_type.view_of(&bytes)),
                         ).unwrap();
                     }
+                });
+
+                Texture::apply_default_parameters(&gl, filter, wrap);
+            }
+        );
+
+        result.warn_incomplete_power_of_two();
+        return result;
+    }
+
+    /**
+     * Like `new`, but straight alpha `Rgba` byte content is premultiplied on the CPU before the upload,
+     * for blending that expects premultiplied colors. Other content is uploaded as is.
+     */
+    pub fn new_premultiplied(gl: Gl, width: u32, height: u32, data_type: TextureType, format: TextureFormat, data: TextureContent) -> Texture {
+        let data = data.premultiply_alpha(format, data_type);
+        Texture::new(gl, width, height, data_type, format, data)
+    }
+
+    /**
+     * Allocate an immutable texture with `levels` mip levels using `tex_storage_2d` and upload the base level
+     * with `tex_sub_image_2d`. Falls back to `Texture::new` on WebGL 1 and for formats without a sized variant.
+     */
+    pub fn new_immutable(
+        gl: Gl,
+        levels: u32,
+        width: u32,
+        height: u32,
+        data_type: TextureType,
+        format: TextureFormat,
+        data: TextureContent,
+    ) -> Result<Texture, GlError> {
+        let count = Texture::full_mip_chain(width, height);
+        if levels == 0 || levels > count {
+            return Err(GlError::MipLevelOutOfRange {
+                level: levels,
+                count: count,
+            });
+        }
+        let (context2, internal_format) = match (gl.context2(), format.sized_internal_format(data_type)) {
+            (Some(context2), Some(internal_format)) => (context2.clone(), internal_format),
+            _ => return Ok(Texture::new(gl, width, height, data_type, format, data)),
+        };
+
+        let handle = gl.context().create_texture().unwrap();
+        let wrap = Texture::default_wrap(width, height);
+        let filter = TextureFilter::default();
+        gl.track_resources(|counts| counts.textures += 1);
+        let result = Texture {
+            data: Rc::new(TextureInfo {
+                gl: gl.clone(),
+                id: gl.next_id(),
+                handle: handle,
+                width: width,
+                height: height,
+                data_type: data_type,
+                format: format,
+                filter: Cell::new(filter),
+                min_filter: Cell::new(MinFilter::from(filter)),
+                mipmapped: Cell::new(false),
+                written_levels: Cell::new(1),
+                wrap: Cell::new(wrap),
+                mip_range: Cell::new((0, 1000)),
+                lod_range: Cell::new((-1000.0, 1000.0)),
+                disposed: Cell::new(false),
+                owned: true,
+                storage_levels: Some(levels),
+                label: RefCell::new(None),
+            }),
+        };
+
+        let format: u32 = format.into();
+        gl.apply(
+            Gl::settings().texture(0, result.clone()),
+            || {
+                context2.tex_storage_2d(Context::TEXTURE_2D, levels as i32, internal_format, width as i32, height as i32);
+                match data {
+                    TextureContent::None => {},
+                    TextureContent::Image(image) => {
+                        gl.context().tex_sub_image_2d_with_u32_and_u32_and_image(
+                            Context::TEXTURE_2D,
+                            0,
+                            0,
+                            0,
+                            format,
+                            data_type.into(),
+                            &image,
+                        ).unwrap();
+                    },
+                    TextureContent::Bytes(bytes) => {
+                        gl.context().tex_sub_image_2d_with_i32_and_i32_and_u32_and_type_and_opt_array_buffer_view(
+                            Context::TEXTURE_2D,
+                            0,
+                            0,
+                            0,
+                            width as i32,
+                            height as i32,
+                            format,
+                            data_type.into(),
+                            Some(&data_type.view_of(&bytes)),
+                        ).unwrap();
+                    },
                 };
+
+                Texture::apply_default_parameters(&gl, filter, wrap);
             }
         );
-        
+
+        return Ok(result);
+    }
+
+    /**
+     * 1x1 RGBA texture filled with the color, handy as a placeholder or to tint with a textured program.
+     */
+    pub fn solid<C: Into<Color>>(gl: Gl, color: C) -> Texture {
+        let bytes = color.into().to_u8_array().to_vec();
+        Texture::new(gl, 1, 1, TextureType::Byte, TextureFormat::Rgba, TextureContent::Bytes(bytes))
+    }
+
+    /**
+     * Single channel float texture for data like lookup tables or heightfields, `data` has `width * height` values.
+     * It's `R32F` on WebGL 2 and `Luminance` with `OES_texture_float` on WebGL 1, filtered with `Nearest`
+     * since linear filtering of floats needs another extension.
+     */
+    pub fn data_r32f(gl: Gl, width: u32, height: u32, data: &[f32]) -> Result<Texture, GlError> {
+        let expected = width as usize * height as usize;
+        if data.len() != expected {
+            return Err(GlError::DataSize {
+                expected: expected,
+                actual: data.len(),
+            });
+        }
+        let values = Float32Array::from(data);
+
+        let result = if gl.is_webgl2() {
+            let result = Texture::new_immutable(gl.clone(), 1, width, height, TextureType::Float, TextureFormat::Red, TextureContent::None)?;
+            gl.apply(
+                Gl::settings().texture(0, result.clone()),
+                || {
+                    gl.context().tex_sub_image_2d_with_i32_and_i32_and_u32_and_type_and_opt_array_buffer_view(
+                        Context::TEXTURE_2D,
+                        0,
+                        0,
+                        0,
+                        width as i32,
+                        height as i32,
+                        Context2::RED,
+                        Context::FLOAT,
+                        Some(&values),
+                    ).unwrap();
+                }
+            );
+            result
+        } else {
+            if gl.context().get_extension("OES_texture_float").ok().and_then(|ex| ex).is_none() {
+                return Err(GlError::ExtensionMissing("OES_texture_float"));
+            }
+            let result = Texture::new(gl.clone(), width, height, TextureType::Float, TextureFormat::Luminance, TextureContent::None);
+            gl.apply(
+                Gl::settings().texture(0, result.clone()),
+                || {
+                    gl.context().tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_array_buffer_view(
+                        Context::TEXTURE_2D,
+                        0,
+                        Context::LUMINANCE as i32,
+                        width as i32,
+                        height as i32,
+                        0,
+                        Context::LUMINANCE,
+                        Context::FLOAT,
+                        Some(&values),
+                    ).unwrap();
+                }
+            );
+            result
+        };
+        result.set_filter(TextureFilter::Nearest);
+        return Ok(result);
+    }
+
+    /**
+     * Wrap a texture created outside of the crate. The handle is not deleted on drop or `dispose`,
+     * its owner stays responsible for it. Filter and wrap are read back from GL.
+     */
+    pub fn from_raw(gl: Gl, handle: WebGlTexture, width: u32, height: u32, data_type: TextureType, format: TextureFormat) -> Texture {
+        gl.track_resources(|counts| counts.textures += 1);
+        let result = Texture {
+            data: Rc::new(TextureInfo {
+                gl: gl.clone(),
+                id: gl.next_id(),
+                handle: handle,
+                width: width,
+                height: height,
+                data_type: data_type,
+                format: format,
+                filter: Default::default(),
+                min_filter: Cell::new(MinFilter::from(TextureFilter::default())),
+                mipmapped: Cell::new(false),
+                written_levels: Cell::new(1),
+                wrap: Default::default(),
+                mip_range: Cell::new((0, 1000)),
+                lod_range: Cell::new((-1000.0, 1000.0)),
+                disposed: Cell::new(false),
+                owned: false,
+                storage_levels: None,
+                label: RefCell::new(None),
+            }),
+        };
+
+        gl.apply(
+            Gl::settings().texture(0, result.clone()),
+            || {
+                let parameter = |name: u32| gl.context().get_tex_parameter(Context::TEXTURE_2D, name).as_f64();
+                if let Some(filter) = parameter(Context::TEXTURE_MAG_FILTER).and_then(|v| TextureFilter::try_from(v as i32).ok()) {
+                    result.data.filter.set(filter);
+                }
+                if let Some(filter) = parameter(Context::TEXTURE_MIN_FILTER).and_then(|v| MinFilter::try_from(v as i32).ok()) {
+                    result.data.min_filter.set(filter);
+                    // Assume a foreign texture with a mipmap filter has its mips
+                    result.data.mipmapped.set(filter.is_mipmap());
+                }
+                if let Some(wrap) = parameter(Context::TEXTURE_WRAP_S).and_then(|v| TextureWrap::try_from(v as i32).ok()) {
+                    result.data.wrap.set(wrap);
+                }
+            }
+        );
+
+        result.warn_incomplete_power_of_two();
         return result;
     }
 
+    /**
+     * Load an image and create a texture of its natural size once it's loaded.
+     */
+    pub async fn from_url(gl: Gl, url: &str, data_type: TextureType, format: TextureFormat) -> Result<Texture, GlError> {
+        let image = HtmlImageElement::new().unwrap();
+        image.set_cross_origin(Some("anonymous"));
+        let loaded = Promise::new(&mut |resolve, reject| {
+            image.set_onload(Some(&resolve));
+            image.set_onerror(Some(&reject));
+        });
+        image.set_src(url);
+        let loaded = JsFuture::from(loaded).await;
+        image.set_onload(None);
+        image.set_onerror(None);
+        loaded.map_err(|_| GlError::ImageLoad(url.to_string()))?;
+
+        let (width, height) = (image.natural_width(), image.natural_height());
+        return Ok(Texture::new(gl, width, height, data_type, format, TextureContent::Image(image)));
+    }
+
     pub fn gl(&self) -> Gl {
         self.data.gl.clone()
     }
@@ -168,22 +648,637 @@ impl Texture {
         (self.width(), self.height())
     }
 
+    /**
+     * Id increasing in creation order, a cheap key for sorting draws by texture.
+     */
+    pub fn id(&self) -> u64 {
+        self.data.id
+    }
+
+    pub fn label(&self) -> Option<String> {
+        self.data.label.borrow().clone()
+    }
+
+    /**
+     * Name shown in `Debug` output and in the NPOT warnings of debug contexts.
+     */
+    pub fn set_label(&self, label: &str) {
+        *self.data.label.borrow_mut() = Some(label.to_string());
+    }
+
+    /**
+     * With `Gl::debug` on, warn when WebGL 1 samples the non power of two texture as black
+     * because of its wrap or mipmap filter.
+     */
+    pub(self) fn warn_incomplete_power_of_two(&self) {
+        let ref gl = self.data.gl;
+        if !gl.debug() || gl.is_webgl2() || self.is_power_of_two() {
+            return;
+        }
+        if self.wrap() != TextureWrap::ClampToEdge || self.min_filter().is_mipmap() {
+            console::warn_1(&format!(
+                "rwgl: {} is not power of two, WebGL 1 samples it as black with {:?} wrap and {:?} min filter, \
+                use ClampToEdge and a filter without mipmaps",
+                self.describe(), self.wrap(), self.min_filter()
+            ).into());
+        }
+    }
+
+    pub(super) fn describe(&self) -> String {
+        match self.label() {
+            Some(label) => format!("texture '{}' {}x{}", label, self.width(), self.height()),
+            None => format!("texture {}x{}", self.width(), self.height()),
+        }
+    }
+
+    /**
+     * New texture of the same size, type and format with a copy of the base level, e.g. a snapshot
+     * of a render target for refraction. The source is read through the scratch framebuffer, so it has to be
+     * color renderable, otherwise `FramebufferIncomplete` is returned.
+     */
+    pub fn duplicate(&self) -> Result<Texture, GlError> {
+        if self.is_disposed() {
+            return Err(GlError::Disposed);
+        }
+        let ref gl = self.data.gl;
+        let (width, height) = self.size();
+        let result = Texture::new(gl.clone(), width, height, self.data_type(), self.format(), TextureContent::None);
+        result.set_filter(self.filter());
+        result.set_wrap(self.wrap());
+
+        gl.with_scratch_framebuffer(self, || {
+            gl.apply(Gl::settings().texture(0, result.clone()), || {
+                gl.context().copy_tex_sub_image_2d(
+                    Context::TEXTURE_2D,
+                    0,
+                    0,
+                    0,
+                    0,
+                    0,
+                    width as i32,
+                    height as i32,
+                );
+            });
+        })?;
+        return Ok(result);
+    }
+
+    /**
+     * Whether sampling the texture gives its content. Incomplete textures sample as black without a GL error.
+     */
+    pub fn is_complete(&self) -> bool {
+        self.incomplete_reason().is_none()
+    }
+
+    /**
+     * Why the texture would sample as black, `None` if it's complete. Checks the state tracked by the crate.
+     */
+    pub fn incomplete_reason(&self) -> Option<&'static str> {
+        let ref gl = self.data.gl;
+        if self.is_disposed() {
+            return Some("texture is disposed");
+        }
+        if self.width() == 0 || self.height() == 0 {
+            return Some("texture has zero size");
+        }
+        if !gl.is_webgl2() && !self.is_power_of_two() && self.wrap() != TextureWrap::ClampToEdge {
+            return Some("non power of two texture must use clamp to edge wrap in WebGL 1");
+        }
+        let linear = self.filter() == TextureFilter::Linear || self.min_filter().is_linear();
+        if self.data_type() == TextureType::Float && linear && !self.supports_float_linear() {
+            return Some("linear filtering of float textures requires OES_texture_float_linear");
+        }
+        if self.min_filter().is_mipmap() && !self.data.mipmapped.get() {
+            return Some("mipmap min filter is used but mipmaps were not generated");
+        }
+        return None;
+    }
+
+    pub fn is_power_of_two(&self) -> bool {
+        self.width().is_power_of_two() && self.height().is_power_of_two()
+    }
+
+    /**
+     * Delete the GL texture right away instead of waiting for the last clone to drop.
+     * Any later upload returns `GlError::Disposed`, binding it binds nothing.
+     */
+    pub fn dispose(&self) {
+        if !self.data.disposed.replace(true) && self.data.owned {
+            self.data.gl.context().delete_texture(Some(&self.data.handle));
+        }
+    }
+
+    pub fn is_disposed(&self) -> bool {
+        self.data.disposed.get()
+    }
+
+    /**
+     * Whether the texture was allocated with `tex_storage_2d` by `new_immutable`, its size and level count are fixed.
+     */
+    pub fn is_immutable(&self) -> bool {
+        self.data.storage_levels.is_some()
+    }
+
+    /**
+     * Number of levels in a full mip chain of the texture size, including the base level.
+     */
+    pub fn mip_level_count(&self) -> u32 {
+        Texture::full_mip_chain(self.width(), self.height())
+    }
+
+    pub(self) fn full_mip_chain(width: u32, height: u32) -> u32 {
+        let size = std::cmp::max(std::cmp::max(width, height), 1);
+        return 32 - size.leading_zeros();
+    }
+
+    /**
+     * `Repeat`, or `ClampToEdge` for NPOT sizes, WebGL 1 samples NPOT textures as black unless they are clamped to edge.
+     */
+    pub(self) fn default_wrap(width: u32, height: u32) -> TextureWrap {
+        return if width.is_power_of_two() && height.is_power_of_two() {
+            TextureWrap::Repeat
+        } else {
+            TextureWrap::ClampToEdge
+        };
+    }
+
+    /**
+     * Set filter and wrap of the texture bound to `TEXTURE_2D`. GL defaults to a mipmap min filter,
+     * which leaves a texture without mips incomplete, so new textures always get these.
+     */
+    pub(self) fn apply_default_parameters(gl: &Gl, filter: TextureFilter, wrap: TextureWrap) {
+        let context = gl.context();
+        context.tex_parameteri(Context::TEXTURE_2D, Context::TEXTURE_MAG_FILTER, filter.into());
+        context.tex_parameteri(Context::TEXTURE_2D, Context::TEXTURE_MIN_FILTER, filter.into());
+        context.tex_parameteri(Context::TEXTURE_2D, Context::TEXTURE_WRAP_S, wrap.into());
+        context.tex_parameteri(Context::TEXTURE_2D, Context::TEXTURE_WRAP_T, wrap.into());
+    }
+
+    /**
+     * Upload a precomputed mip level. Level dimensions must be the base size shifted by `level`, clamped to 1.
+     */
+    pub fn write_mip_level(&self, level: u32, width: u32, height: u32, data: &[u8]) -> Result<(), GlError> {
+        if self.is_disposed() {
+            return Err(GlError::Disposed);
+        }
+        // Immutable textures only have the levels allocated by `tex_storage_2d`
+        let count = self.data.storage_levels.unwrap_or(self.mip_level_count());
+        if level >= count {
+            return Err(GlError::MipLevelOutOfRange {
+                level: level,
+                count: count,
+            });
+        }
+        let expected = (
+            std::cmp::max(self.width().checked_shr(level).unwrap_or(0), 1),
+            std::cmp::max(self.height().checked_shr(level).unwrap_or(0), 1),
+        );
+        if (width, height) != expected {
+            return Err(GlError::InvalidMipLevel {
+                level: level,
+                expected: expected,
+                actual: (width, height),
+            });
+        }
+        self.check_upload_size(width, height, data)?;
+
+        let ref gl = self.data.gl;
+        let format: u32 = self.format().into();
+        let data = self.data_type().view_of(data);
+        gl.apply(
+            Gl::settings().texture(0, self.clone()),
+            || {
+                if self.is_immutable() {
+                    gl.context().tex_sub_image_2d_with_i32_and_i32_and_u32_and_type_and_opt_array_buffer_view(
+                        Context::TEXTURE_2D,
+                        level as i32,
+                        0,
+                        0,
+                        width as i32,
+                        height as i32,
+                        format,
+                        self.data_type().into(),
+                        Some(&data),
+                    ).unwrap();
+                } else {
+                    gl.context().tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_array_buffer_view(
+                        Context::TEXTURE_2D,
+                        level as i32,
+                        format as i32,
+                        width as i32,
+                        height as i32,
+                        0,
+                        format,
+                        self.data_type().into(),
+                        Some(&data),
+                    ).unwrap();
+                }
+            }
+        );
+
+        // Every level of the chain uploaded by hand is as good as generated mips
+        let written = self.data.written_levels.get() | (1 << level);
+        self.data.written_levels.set(written);
+        if written.count_ones() >= count {
+            self.data.mipmapped.set(true);
+        }
+        return Ok(());
+    }
+
+    /**
+     * Replace a rectangle of the base level. `data` is tightly packed unless an `unpack_region` setting
+     * is applied, then it can be a larger image the rectangle is read out of. Fails with `SubImageRange`
+     * if the rectangle doesn't fit the texture and with `DataSize` if `data` doesn't have the pixels.
+     */
+    pub fn write_sub_image(&self, x: u32, y: u32, width: u32, height: u32, data: &[u8]) -> Result<(), GlError> {
+        self.write_sub_image_with_flip_y(x, y, width, height, data, false)
+    }
+
+    /**
+     * Like `write_sub_image`, with `flip_y` the rows of `data` are written bottom up. The flip applies
+     * to this upload only.
+     */
+    pub fn write_sub_image_with_flip_y(
+        &self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        data: &[u8],
+        flip_y: bool,
+    ) -> Result<(), GlError> {
+        if self.is_disposed() {
+            return Err(GlError::Disposed);
+        }
+        let fits = |offset: u32, len: u32, size: u32| offset.checked_add(len).map_or(false, |end| end <= size);
+        if !fits(x, width, self.width()) || !fits(y, height, self.height()) {
+            return Err(GlError::SubImageRange {
+                x: x,
+                y: y,
+                width: width,
+                height: height,
+                size: (self.width(), self.height()),
+            });
+        }
+        self.check_upload_size(width, height, data)?;
+        let ref gl = self.data.gl;
+        let data = self.data_type().view_of(data);
+        gl.apply(
+            Gl::settings().texture(0, self.clone()),
+            || Texture::with_flip_y(gl, flip_y, || {
+                gl.context().tex_sub_image_2d_with_i32_and_i32_and_u32_and_type_and_opt_array_buffer_view(
+                    Context::TEXTURE_2D,
+                    0,
+                    x as i32,
+                    y as i32,
+                    width as i32,
+                    height as i32,
+                    self.format().into(),
+                    self.data_type().into(),
+                    Some(&data),
+                ).unwrap();
+            })
+        );
+        return Ok(());
+    }
+
+    /**
+     * Check that `data` has the `width` x `height` pixels of an upload, tightly packed or spanning
+     * the applied unpack region.
+     */
+    pub(self) fn check_upload_size(&self, width: u32, height: u32, data: &[u8]) -> Result<(), GlError> {
+        let pixel = (self.format().channels() * self.data_type().bytes_per_channel()) as usize;
+        return match UnpackRegionSetting::applied_span(&self.data.gl, width, height) {
+            Some(span) if data.len() < span * pixel => Err(GlError::DataSize {
+                expected: span * pixel,
+                actual: data.len(),
+            }),
+            Some(_) => Ok(()),
+            None if data.len() != width as usize * height as usize * pixel => Err(GlError::DataSize {
+                expected: width as usize * height as usize * pixel,
+                actual: data.len(),
+            }),
+            None => Ok(()),
+        };
+    }
+
+    /**
+     * Set `UNPACK_FLIP_Y_WEBGL` around the upload, it's off everywhere else.
+     */
+    pub(self) fn with_flip_y<R, F: FnOnce() -> R>(gl: &Gl, flip_y: bool, upload: F) -> R {
+        if !flip_y {
+            return upload();
+        }
+        gl.context().pixel_storei(Context::UNPACK_FLIP_Y_WEBGL, 1);
+        let result = upload();
+        gl.context().pixel_storei(Context::UNPACK_FLIP_Y_WEBGL, 0);
+        return result;
+    }
+
+    /**
+     * Generate the mip chain from the base level, returns the number of levels it has.
+     */
+    pub fn generate_mipmap(&self) -> Result<u32, GlError> {
+        if self.is_disposed() {
+            return Err(GlError::Disposed);
+        }
+        if !self.data.gl.is_webgl2() && !self.is_power_of_two() {
+            return Err(GlError::NonPowerOfTwo {
+                width: self.width(),
+                height: self.height(),
+            });
+        }
+        let ref gl = self.data.gl;
+        gl.apply(
+            Gl::settings().texture(0, self.clone()),
+            || gl.context().generate_mipmap(Context::TEXTURE_2D)
+        );
+        self.data.mipmapped.set(true);
+        return Ok(self.data.storage_levels.unwrap_or(self.mip_level_count()));
+    }
+
+    /**
+     * Mip levels sampling is restricted to, `(0, 1000)` by default like in GL.
+     */
+    pub fn mip_range(&self) -> (u32, u32) {
+        self.data.mip_range.get()
+    }
+
+    /**
+     * Restrict sampling to levels `base..=max`, e.g. while streaming mipmaps only some levels are resident.
+     * Fails with `InvalidMipRange` if `base` is above `max`.
+     */
+    pub fn set_mip_range(&self, base: u32, max: u32) -> Result<(), GlError> {
+        if !self.data.gl.is_webgl2() {
+            return Err(GlError::WebGl2Required);
+        }
+        if base > max {
+            return Err(GlError::InvalidMipRange {
+                base: base,
+                max: max,
+            });
+        }
+        if self.is_disposed() {
+            return Err(GlError::Disposed);
+        }
+        if self.mip_range() != (base, max) {
+            let ref gl = self.data.gl;
+            gl.apply(
+                Gl::settings().texture(0, self.clone()),
+                || {
+                    gl.context().tex_parameteri(Context::TEXTURE_2D, Context2::TEXTURE_BASE_LEVEL, base as i32);
+                    gl.context().tex_parameteri(Context::TEXTURE_2D, Context2::TEXTURE_MAX_LEVEL, max as i32);
+                }
+            );
+            self.data.mip_range.set((base, max));
+        }
+        return Ok(());
+    }
+
+    /**
+     * Level of detail sampling is clamped to, `(-1000, 1000)` by default like in GL.
+     */
+    pub fn lod_range(&self) -> (f32, f32) {
+        self.data.lod_range.get()
+    }
+
+    /**
+     * Clamp the level of detail, a minimum above 0 forces coarser mips e.g. for blurred distant content.
+     * WebGL has no `TEXTURE_LOD_BIAS` parameter, a bias is passed to `texture` in the shader instead,
+     * which is also the only way to control the level of detail on WebGL 1. Fails with `InvalidLodRange`
+     * unless `min <= max`, which also rejects NaN.
+     */
+    pub fn set_lod_range(&self, min: f32, max: f32) -> Result<(), GlError> {
+        if !self.data.gl.is_webgl2() {
+            return Err(GlError::WebGl2Required);
+        }
+        if min.is_nan() || max.is_nan() || min > max {
+            return Err(GlError::InvalidLodRange);
+        }
+        if self.is_disposed() {
+            return Err(GlError::Disposed);
+        }
+        if self.lod_range() != (min, max) {
+            let ref gl = self.data.gl;
+            gl.apply(
+                Gl::settings().texture(0, self.clone()),
+                || {
+                    gl.context().tex_parameterf(Context::TEXTURE_2D, Context2::TEXTURE_MIN_LOD, min);
+                    gl.context().tex_parameterf(Context::TEXTURE_2D, Context2::TEXTURE_MAX_LOD, max);
+                }
+            );
+            self.data.lod_range.set((min, max));
+        }
+        return Ok(());
+    }
+
     pub fn filter(&self) -> TextureFilter {
         self.data.filter.get()
     }
 
     pub fn set_filter(&self, filter: TextureFilter) {
-        if self.filter() != filter {
+        let changed = self.filter() != filter || self.min_filter() != MinFilter::from(filter);
+        if changed && !self.is_disposed() {
             let ref gl = self.data.gl;
             let context = gl.context();
             gl.apply(
                 Gl::settings().texture(0, self.clone()),
                 || {
-                    context.tex_parameteri(Context::TEXTURE0, Context::TEXTURE_MAG_FILTER, filter.into());
-                    context.tex_parameteri(Context::TEXTURE0, Context::TEXTURE_MIN_FILTER, filter.into());
+                    context.tex_parameteri(Context::TEXTURE_2D, Context::TEXTURE_MAG_FILTER, filter.into());
+                    context.tex_parameteri(Context::TEXTURE_2D, Context::TEXTURE_MIN_FILTER, filter.into());
                     self.data.filter.set(filter);
+                    self.data.min_filter.set(MinFilter::from(filter));
+                }
+            );
+        }
+    }
+
+    pub fn min_filter(&self) -> MinFilter {
+        self.data.min_filter.get()
+    }
+
+    /**
+     * Set the minification filter only. Combinations that would sample as black fall back to the closest
+     * valid filter with a warning: mipmap filters without generated mips or on a NPOT texture in WebGL 1,
+     * linear filters on float textures without `OES_texture_float_linear`.
+     */
+    pub fn set_min_filter(&self, filter: MinFilter) {
+        let mut applied = filter;
+        if applied.is_mipmap() && !self.data.mipmapped.get() {
+            applied = applied.without_mipmap();
+        }
+        if applied.is_mipmap() && !self.data.gl.is_webgl2() && !self.is_power_of_two() {
+            applied = applied.without_mipmap();
+        }
+        if applied.is_linear() && self.data_type() == TextureType::Float && !self.supports_float_linear() {
+            applied = applied.without_linear();
+        }
+        if applied != filter {
+            console::warn_1(&format!(
+                "Min filter {:?} is not valid for {}, using {:?}",
+                filter, self.describe(), applied
+            ).into());
+        }
+
+        if self.min_filter() != applied && !self.is_disposed() {
+            let ref gl = self.data.gl;
+            gl.apply(
+                Gl::settings().texture(0, self.clone()),
+                || {
+                    gl.context().tex_parameteri(Context::TEXTURE_2D, Context::TEXTURE_MIN_FILTER, applied.into());
+                }
+            );
+            self.data.min_filter.set(applied);
+        }
+        self.warn_incomplete_power_of_two();
+    }
+
+    pub(self) fn supports_float_linear(&self) -> bool {
+        self.data.gl.context().get_extension("OES_texture_float_linear").ok().and_then(|ex| ex).is_some()
+    }
+
+    pub fn wrap(&self) -> TextureWrap {
+        self.data.wrap.get()
+    }
+
+    /**
+     * Set wrap mode for both axes. Repeat modes on a NPOT texture are replaced with
+     * `ClampToEdge` with a warning, use `try_set_wrap` to get an error instead.
+     */
+    pub fn set_wrap(&self, wrap: TextureWrap) {
+        let wrap = if self.is_power_of_two() || wrap == TextureWrap::ClampToEdge {
+            wrap
+        } else {
+            console::warn_1(&format!(
+                "rwgl: {} is not power of two, using ClampToEdge instead of {:?}",
+                self.describe(), wrap
+            ).into());
+            TextureWrap::ClampToEdge
+        };
+        self.write_wrap(wrap);
+    }
+
+    pub fn try_set_wrap(&self, wrap: TextureWrap) -> Result<(), GlError> {
+        if !self.is_power_of_two() && wrap != TextureWrap::ClampToEdge {
+            return Err(GlError::NonPowerOfTwo {
+                width: self.width(),
+                height: self.height(),
+            });
+        }
+        self.write_wrap(wrap);
+        self.warn_incomplete_power_of_two();
+        return Ok(());
+    }
+
+    pub(self) fn write_wrap(&self, wrap: TextureWrap) {
+        if self.wrap() != wrap && !self.is_disposed() {
+            let ref gl = self.data.gl;
+            let context = gl.context();
+            gl.apply(
+                Gl::settings().texture(0, self.clone()),
+                || {
+                    context.tex_parameteri(Context::TEXTURE_2D, Context::TEXTURE_WRAP_S, wrap.into());
+                    context.tex_parameteri(Context::TEXTURE_2D, Context::TEXTURE_WRAP_T, wrap.into());
+                    self.data.wrap.set(wrap);
                 }
             );
         }
     }
+
+    /**
+     * Restore the filter and wrap the texture was created with, `TextureFilter::default()` and `Repeat`,
+     * or `ClampToEdge` for NPOT sizes. The GL calls are issued even if the cached values already match,
+     * in case the parameters were changed behind the crate's back.
+     */
+    pub fn reset_parameters(&self) {
+        if self.is_disposed() {
+            return;
+        }
+        let filter = TextureFilter::default();
+        let wrap = Texture::default_wrap(self.width(), self.height());
+        let ref gl = self.data.gl;
+        gl.apply(
+            Gl::settings().texture(0, self.clone()),
+            || Texture::apply_default_parameters(gl, filter, wrap)
+        );
+        self.data.filter.set(filter);
+        self.data.min_filter.set(MinFilter::from(filter));
+        self.data.wrap.set(wrap);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_test::*;
+    use web_sys::HtmlCanvasElement;
+
+    use super::*;
+    use super::super::framebuffer::Framebuffer;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn create_gl2() -> Gl {
+        let canvas = web_sys::window().unwrap()
+            .document().unwrap()
+            .create_element("canvas").unwrap()
+            .dyn_into::<HtmlCanvasElement>().unwrap();
+        return Gl::new_webgl2(&canvas).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn float_content_is_uploaded() {
+        let gl = create_gl2();
+        // Float color attachments aren't renderable, so can't be read back, without the extension
+        if gl.context().get_extension("EXT_color_buffer_float").ok().and_then(|ex| ex).is_none() {
+            return;
+        }
+        let values = [0.25f32, 0.5, 1.5, -2.0];
+        let texture = Texture::new(
+            gl.clone(), 1, 1, TextureType::Float, TextureFormat::Rgba, TextureContent::from_data(&values)
+        );
+        assert_eq!(gl.context().get_error(), Context::NO_ERROR);
+
+        let framebuffer = Framebuffer::new(gl.clone(), texture).unwrap();
+        let pixels = Float32Array::new_with_length(4);
+        gl.apply(Gl::settings().framebuffer(framebuffer), || {
+            gl.context().read_pixels_with_opt_array_buffer_view(0, 0, 1, 1, Context::RGBA, Context::FLOAT, Some(&pixels)).unwrap();
+        });
+        assert_eq!(pixels.to_vec(), values.to_vec());
+    }
+
+    fn premultiplied(bytes: Vec<u8>, format: TextureFormat, data_type: TextureType) -> Vec<u8> {
+        match TextureContent::Bytes(bytes).premultiply_alpha(format, data_type) {
+            TextureContent::Bytes(bytes) => bytes,
+            content => panic!("bytes became {:?}", content),
+        }
+    }
+
+    #[test]
+    fn premultiply_rounds_to_nearest() {
+        let bytes = vec![255, 128, 1, 128, 200, 100, 50, 255];
+        assert_eq!(
+            premultiplied(bytes, TextureFormat::Rgba, TextureType::Byte),
+            vec![128, 64, 1, 128, 200, 100, 50, 255]
+        );
+    }
+
+    #[test]
+    fn premultiply_clears_transparent_pixels() {
+        let bytes = vec![255, 255, 255, 0];
+        assert_eq!(premultiplied(bytes, TextureFormat::Rgba, TextureType::Byte), vec![0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn premultiply_keeps_formats_without_alpha() {
+        let bytes = vec![255, 128, 64, 32, 16, 8];
+        assert_eq!(premultiplied(bytes.clone(), TextureFormat::Rgb, TextureType::Byte), bytes);
+        assert_eq!(premultiplied(bytes.clone(), TextureFormat::LuminanceAlpha, TextureType::Byte), bytes);
+    }
+
+    #[test]
+    fn premultiply_keeps_float_content() {
+        let bytes = bytes_of(&[1.0f32, 0.5, 0.25, 0.5]).to_vec();
+        assert_eq!(premultiplied(bytes.clone(), TextureFormat::Rgba, TextureType::Float), bytes);
+    }
 }
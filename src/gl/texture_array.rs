@@ -0,0 +1,196 @@
+use std::rc::Rc;
+use web_sys::{
+    WebGlTexture,
+    WebGlRenderingContext as Context,
+    WebGl2RenderingContext as Context2,
+};
+
+use super::gl::Gl;
+use super::settings::Settings;
+use super::error::GlError;
+use super::texture::TextureType;
+use super::texture::TextureFormat;
+use super::texture::TextureContent;
+use super::texture::TextureFilter;
+use super::texture::TextureWrap;
+
+#[derive(Debug)]
+pub struct TextureArrayInfo {
+    pub(self) gl: Gl,
+    pub(super) handle: WebGlTexture,
+    pub(self) width: u32,
+    pub(self) height: u32,
+    pub(self) layers: u32,
+    pub(self) data_type: TextureType,
+    pub(self) format: TextureFormat,
+}
+
+impl PartialEq<TextureArrayInfo> for TextureArrayInfo {
+    fn eq(&self, other: &TextureArrayInfo) -> bool {
+        self.handle == other.handle
+    }
+}
+
+impl Eq for TextureArrayInfo {}
+
+impl Drop for TextureArrayInfo {
+    fn drop(&mut self) {
+        self.gl.context().delete_texture(Some(&self.handle));
+        self.gl.track_resources(|counts| counts.texture_arrays = counts.texture_arrays.saturating_sub(1));
+        if let Ok(mut cache) = self.gl.settings_cache().try_borrow_mut() {
+            cache.evict_dropped_textures();
+        }
+    }
+}
+
+/**
+ * WebGL 2 array of same sized layers sampled with `sampler2DArray`, bind it with
+ * `Gl::settings().texture_array(unit, array)`.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextureArray {
+    pub(super) data: Rc<TextureArrayInfo>,
+}
+
+impl TextureArray {
+    pub fn new(gl: Gl, width: u32, height: u32, layers: u32, data_type: TextureType, format: TextureFormat) -> Result<TextureArray, GlError> {
+        let context2 = gl.context2().ok_or(GlError::WebGl2Required)?.clone();
+        let handle = context2.create_texture().unwrap();
+        gl.track_resources(|counts| counts.texture_arrays += 1);
+        let result = TextureArray {
+            data: Rc::new(TextureArrayInfo {
+                gl: gl.clone(),
+                handle: handle,
+                width: width,
+                height: height,
+                layers: layers,
+                data_type: data_type,
+                format: format,
+            }),
+        };
+
+        let filter = TextureFilter::default();
+        let wrap = TextureWrap::ClampToEdge;
+        gl.apply(
+            Gl::settings().texture_array(0, result.clone()),
+            || {
+                match format.sized_internal_format(data_type) {
+                    Some(internal_format) => context2.tex_storage_3d(
+                        Context2::TEXTURE_2D_ARRAY,
+                        1,
+                        internal_format,
+                        width as i32,
+                        height as i32,
+                        layers as i32,
+                    ),
+                    // Unsized formats like luminance can't be used for immutable storage
+                    None => context2.tex_image_3d_with_opt_u8_array(
+                        Context2::TEXTURE_2D_ARRAY,
+                        0,
+                        u32::from(format) as i32,
+                        width as i32,
+                        height as i32,
+                        layers as i32,
+                        0,
+                        format.into(),
+                        data_type.into(),
+                        None,
+                    ).unwrap(),
+                }
+                context2.tex_parameteri(Context2::TEXTURE_2D_ARRAY, Context::TEXTURE_MAG_FILTER, filter.into());
+                context2.tex_parameteri(Context2::TEXTURE_2D_ARRAY, Context::TEXTURE_MIN_FILTER, filter.into());
+                context2.tex_parameteri(Context2::TEXTURE_2D_ARRAY, Context::TEXTURE_WRAP_S, wrap.into());
+                context2.tex_parameteri(Context2::TEXTURE_2D_ARRAY, Context::TEXTURE_WRAP_T, wrap.into());
+            }
+        );
+
+        return Ok(result);
+    }
+
+    pub fn gl(&self) -> Gl {
+        self.data.gl.clone()
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.data.width, self.data.height)
+    }
+
+    pub fn layers(&self) -> u32 {
+        self.data.layers
+    }
+
+    pub fn data_type(&self) -> TextureType {
+        self.data.data_type
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.data.format
+    }
+
+    /**
+     * Replace the content of the layer, the image or bytes must have the size of the array.
+     */
+    pub fn write_layer(&self, layer: u32, content: TextureContent) -> Result<(), GlError> {
+        if layer >= self.layers() {
+            return Err(GlError::LayerOutOfRange {
+                layer: layer,
+                layers: self.layers(),
+            });
+        }
+        if let TextureContent::Bytes(ref bytes) = content {
+            let (width, height) = self.size();
+            let expected = width as usize * height as usize
+                * self.format().channels() as usize * self.data_type().bytes_per_channel() as usize;
+            if bytes.len() != expected {
+                return Err(GlError::DataSize {
+                    expected: expected,
+                    actual: bytes.len(),
+                });
+            }
+        }
+        let ref gl = self.data.gl;
+        let context2 = gl.context2().ok_or(GlError::WebGl2Required)?;
+        let (width, height) = self.size();
+        let format: u32 = self.format().into();
+        let data_type: u32 = self.data_type().into();
+        gl.apply(
+            Gl::settings().texture_array(0, self.clone()),
+            || {
+                match content {
+                    TextureContent::None => {},
+                    TextureContent::Image(image) => {
+                        context2.tex_sub_image_3d_with_html_image_element(
+                            Context2::TEXTURE_2D_ARRAY,
+                            0,
+                            0,
+                            0,
+                            layer as i32,
+                            width as i32,
+                            height as i32,
+                            1,
+                            format,
+                            data_type,
+                            &image,
+                        ).unwrap();
+                    },
+                    TextureContent::Bytes(bytes) => {
+                        context2.tex_sub_image_3d_with_opt_u8_array(
+                            Context2::TEXTURE_2D_ARRAY,
+                            0,
+                            0,
+                            0,
+                            layer as i32,
+                            width as i32,
+                            height as i32,
+                            1,
+                            format,
+                            data_type,
+                            Some(&bytes[..]),
+                        ).unwrap();
+                    },
+                }
+            }
+        );
+        return Ok(());
+    }
+}
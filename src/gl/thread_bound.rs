@@ -0,0 +1,57 @@
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+use super::gl::Gl;
+
+/**
+ * Value pinned to the thread it was created on. WebGL contexts and everything created from them
+ * belong to the thread (main or worker with `OffscreenCanvas`) that owns the canvas.
+ * The raw pointer marker makes the wrapper `!Send` and `!Sync` whatever the wrapped type is.
+ */
+#[derive(Clone, Debug)]
+pub struct ThreadBound<T> {
+    pub(self) value: T,
+    pub(self) marker: PhantomData<*const ()>,
+}
+
+impl <T> ThreadBound<T> {
+    pub fn new(value: T) -> ThreadBound<T> {
+        ThreadBound {
+            value: value,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl <T> Deref for ThreadBound<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+/**
+ * Compile time check that `Gl` is not `Send`. If it ever becomes `Send`,
+ * both impls below apply to it and the call in `assert_gl_is_not_send` is ambiguous.
+ */
+trait AmbiguousIfSend<A> {
+    fn check() {}
+}
+
+impl <T: ?Sized> AmbiguousIfSend<()> for T {}
+
+#[allow(dead_code)]
+struct IsSend;
+
+impl <T: ?Sized + Send> AmbiguousIfSend<IsSend> for T {}
+
+#[allow(dead_code)]
+fn assert_gl_is_not_send() {
+    <Gl as AmbiguousIfSend<_>>::check();
+    <ThreadBound<()> as AmbiguousIfSend<_>>::check();
+}